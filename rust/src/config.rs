@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::fs;
 use std::path::{Path, PathBuf};
 
@@ -82,6 +83,73 @@ pub struct Config {
     pub edit_app: String,
     #[serde(default)]
     pub debug_print_system: bool,
+    #[serde(default)]
+    pub diff_side_by_side: bool,
+    // GitHistory shows "3 days ago" instead of the commit's date.
+    #[serde(default)]
+    pub history_relative_dates: bool,
+    #[serde(default = "default_commit_prefixes")]
+    pub commit_prefixes: Vec<String>,
+    #[serde(default)]
+    pub commit_rules: CommitRules,
+    // Per-repo rules keyed by repo path; set fields override `commit_rules`.
+    #[serde(default)]
+    pub repo_commit_rules: BTreeMap<String, CommitRules>,
+    // Commit screen toggles remembered per repo path.
+    #[serde(default)]
+    pub repo_commit_options: BTreeMap<String, CommitOptions>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CommitOptions {
+    #[serde(default)]
+    pub signoff: bool,
+    #[serde(default)]
+    pub sign: bool,
+    // Full trailer lines such as "Co-authored-by: Name <email>".
+    #[serde(default)]
+    pub trailers: Vec<String>,
+}
+
+// Commit message checks. Every field is optional so an override only replaces what it sets.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct CommitRules {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_subject_length: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub required_pattern: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub forbidden_words: Option<Vec<String>>,
+    // Branches the forbidden words apply to; all branches when unset or empty.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub protected_branches: Option<Vec<String>>,
+    // Violations refuse the commit instead of asking for a second confirm.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub block: Option<bool>,
+}
+
+impl CommitRules {
+    pub fn merged(&self, over: &CommitRules) -> CommitRules {
+        CommitRules {
+            max_subject_length: over.max_subject_length.or(self.max_subject_length),
+            required_pattern: over.required_pattern.clone().or_else(|| self.required_pattern.clone()),
+            forbidden_words: over.forbidden_words.clone().or_else(|| self.forbidden_words.clone()),
+            protected_branches: over
+                .protected_branches
+                .clone()
+                .or_else(|| self.protected_branches.clone()),
+            block: over.block.or(self.block),
+        }
+    }
+}
+
+fn default_commit_prefixes() -> Vec<String> {
+    ["feat:", "fix:", "chore:", "docs:", "refactor:", "test:"]
+        .iter()
+        .map(|s| s.to_string())
+        .collect()
 }
 
 #[derive(Debug, Deserialize)]
@@ -99,6 +167,18 @@ struct RawConfig {
     edit_app: Option<String>,
     #[serde(default)]
     debug_print_system: bool,
+    #[serde(default)]
+    diff_side_by_side: bool,
+    #[serde(default)]
+    history_relative_dates: bool,
+    #[serde(default)]
+    commit_prefixes: Option<Vec<String>>,
+    #[serde(default)]
+    commit_rules: CommitRules,
+    #[serde(default)]
+    repo_commit_rules: BTreeMap<String, CommitRules>,
+    #[serde(default)]
+    repo_commit_options: BTreeMap<String, CommitOptions>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -156,6 +236,12 @@ impl Config {
             grep_app: raw.grep_app.unwrap_or_default(),
             edit_app: raw.edit_app.unwrap_or_default(),
             debug_print_system: raw.debug_print_system,
+            diff_side_by_side: raw.diff_side_by_side,
+            history_relative_dates: raw.history_relative_dates,
+            commit_prefixes: raw.commit_prefixes.unwrap_or_else(default_commit_prefixes),
+            commit_rules: raw.commit_rules,
+            repo_commit_rules: raw.repo_commit_rules,
+            repo_commit_options: raw.repo_commit_options,
         };
         cfg.ensure_defaults();
         Ok((cfg, cfg_path))
@@ -167,6 +253,30 @@ impl Config {
         Ok(())
     }
 
+    pub fn commit_rules_for(&self, repo_root: &Path) -> CommitRules {
+        self.repo_commit_rules
+            .iter()
+            .find(|(path, _)| expand_tilde(path.trim_end_matches('/')) == repo_root)
+            .map(|(_, over)| self.commit_rules.merged(over))
+            .unwrap_or_else(|| self.commit_rules.clone())
+    }
+
+    pub fn commit_options_for(&self, repo_root: &Path) -> CommitOptions {
+        self.repo_commit_options
+            .get(repo_root.to_string_lossy().as_ref())
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    pub fn set_commit_options(&mut self, repo_root: &Path, options: CommitOptions) {
+        let key = repo_root.to_string_lossy().to_string();
+        if options == CommitOptions::default() {
+            self.repo_commit_options.remove(&key);
+        } else {
+            self.repo_commit_options.insert(key, options);
+        }
+    }
+
     fn ensure_defaults(&mut self) {
         if self.grep_app.is_empty() {
             if is_executable_in_path("ag") {
@@ -196,6 +306,12 @@ impl Default for Config {
             grep_app: String::new(),
             edit_app: String::new(),
             debug_print_system: false,
+            diff_side_by_side: false,
+            history_relative_dates: false,
+            commit_prefixes: default_commit_prefixes(),
+            commit_rules: CommitRules::default(),
+            repo_commit_rules: BTreeMap::new(),
+            repo_commit_options: BTreeMap::new(),
         };
         cfg.ensure_defaults();
         cfg
@@ -206,6 +322,26 @@ impl Default for Config {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_commit_rules_repo_override() {
+        let text = r#"{
+            "commitRules": { "maxSubjectLength": 50, "forbiddenWords": ["WIP"] },
+            "repoCommitRules": { "/work/api": { "maxSubjectLength": 72, "block": true } }
+        }"#;
+        let raw: RawConfig = serde_json::from_str(text).unwrap();
+        let cfg = Config {
+            commit_rules: raw.commit_rules,
+            repo_commit_rules: raw.repo_commit_rules,
+            ..Config::default()
+        };
+
+        let rules = cfg.commit_rules_for(Path::new("/work/api"));
+        assert_eq!(rules.max_subject_length, Some(72));
+        assert_eq!(rules.forbidden_words, Some(vec!["WIP".to_string()]));
+        assert_eq!(rules.block, Some(true));
+        assert_eq!(cfg.commit_rules_for(Path::new("/other")), cfg.commit_rules);
+    }
+
     #[test]
     fn test_reg_item_display_name() {
         let item_with_name_match = RegItem {
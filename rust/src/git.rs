@@ -1,4 +1,6 @@
 use regex::Regex;
+use std::cmp::Reverse;
+use std::collections::{BTreeSet, BinaryHeap, HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::ops::Range;
 use std::io::{Write, BufReader, BufRead};
@@ -6,12 +8,10 @@ use std::sync::mpsc;
 
 use gix::bstr::ByteSlice;
 use gix::prelude::TreeDiffChangeExt;
-use gix::revision::walk::Sorting;
-use gix::traverse::commit::simple::CommitTimeOrder;
 
-use crate::system::{system, system_safe, system_logged};
+use crate::system::{log_command_result, system, system_safe, system_logged};
 use crate::config::RegItem;
-use crate::util::unwrap_quotes_filename;
+use crate::util::{format_datetime, format_offset, format_relative, now_secs, unwrap_quotes_filename};
 
 #[derive(Debug, Clone)]
 pub struct BranchStatus {
@@ -44,20 +44,55 @@ pub struct RepoStatusInfo {
     pub behind: usize,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
 pub struct CommitSummary {
+    // Full hash; `short_hash` is for display.
     pub hash: String,
     pub author: String,
+    pub email: String,
+    // Absolute date in the commit's own zone; `time` and `offset` keep the raw values.
     pub date: String,
+    pub time: i64,
+    pub offset: i32,
     pub subject: String,
+    // Full message, for filters that search the body.
+    pub message: String,
+    // Full hashes of the parents the walk goes on to list; those left of a range or cut off
+    // by a shallow clone are dropped so graph lanes don't wait for them.
+    pub parents: Vec<String>,
+    // Has more than one parent, listed or not.
+    pub merge: bool,
 }
 
 impl CommitSummary {
-    pub fn to_list_label(&self) -> String {
-        format!("{} {} {} {}", self.hash, self.date, self.author, self.subject)
+    pub fn short_hash(&self) -> &str {
+        &self.hash[..7.min(self.hash.len())]
+    }
+
+    // Absolute date, or relative to `now` padded to the same width.
+    pub fn date_label(&self, relative: bool, now: i64) -> String {
+        if relative {
+            format!("{:<16}", format_relative(self.time, now))
+        } else {
+            self.date.clone()
+        }
     }
 }
 
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DiffHunk {
+    pub old_start: usize,
+    pub old_count: usize,
+    pub new_start: usize,
+    pub new_count: usize,
+    // Line index of the `@@` header within the parsed diff.
+    pub start: usize,
+    // Exclusive end of the hunk body.
+    pub end: usize,
+    // `diff --git` .. `+++` lines of the file this hunk belongs to.
+    pub file_header: Range<usize>,
+}
+
 fn short_hash(hash: &str) -> String {
     hash.chars().take(7).collect()
 }
@@ -84,6 +119,11 @@ struct UnifiedHunk {
     edits: Vec<UnifiedEdit>,
 }
 
+// `@@` positions are 1-based, except that an empty range names the line before it.
+fn hunk_header_pos(start: usize, count: usize) -> usize {
+    if count == 0 { start } else { start + 1 }
+}
+
 fn emit_unified_hunks(
     lines: &mut Vec<String>,
     old_lines: &[String],
@@ -122,8 +162,8 @@ fn emit_unified_hunks(
     for h in hunks {
         let old_count = h.old_end.saturating_sub(h.old_start);
         let new_count = h.new_end.saturating_sub(h.new_start);
-        let old_pos = if old_count == 0 { h.old_start } else { h.old_start + 1 };
-        let new_pos = if new_count == 0 { h.new_start } else { h.new_start + 1 };
+        let old_pos = hunk_header_pos(h.old_start, old_count);
+        let new_pos = hunk_header_pos(h.new_start, new_count);
         lines.push(format!(
             "@@ -{},{} +{},{} @@",
             old_pos, old_count, new_pos, new_count
@@ -282,6 +322,9 @@ impl RepoStatusInfo {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::system::system;
+    use crate::ui::common::TestEnv;
+    use std::fs;
 
     #[test]
     fn test_repo_status_info_formatting() {
@@ -332,14 +375,20 @@ mod tests {
     }
 
     #[test]
-    fn test_commit_summary_label() {
+    fn test_commit_summary_date_label() {
+        let time = 1_771_131_800;
         let item = CommitSummary {
             hash: "abc1234".to_string(),
             author: "me".to_string(),
-            date: "2026-01-01".to_string(),
+            email: "me@example.com".to_string(),
+            date: format_datetime(time, 0),
+            time,
+            offset: 0,
             subject: "message".to_string(),
+            ..Default::default()
         };
-        assert_eq!(item.to_list_label(), "abc1234 2026-01-01 me message");
+        assert_eq!(item.date_label(false, 0), "2026-02-15 05:03");
+        assert_eq!(item.date_label(true, time + 7200), "2 hours ago     ");
     }
 
     #[test]
@@ -355,6 +404,68 @@ mod tests {
         assert_eq!(first_line("\n\n"), "< no message >");
     }
 
+    #[test]
+    fn test_parse_diff_hunks_and_patch() {
+        let lines: Vec<String> = [
+            "diff --git a/a.txt b/a.txt",
+            "index 1111111..2222222 100644",
+            "--- a/a.txt",
+            "+++ b/a.txt",
+            "@@ -1,3 +1,3 @@",
+            " l1",
+            "-l2",
+            "+l2 changed",
+            "@@ -10 +10,2 @@ fn ctx()",
+            " l10",
+            "+l11",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+
+        let hunks = parse_diff_hunks(&lines);
+        assert_eq!(hunks.len(), 2);
+        assert_eq!((hunks[0].old_start, hunks[0].old_count), (1, 3));
+        assert_eq!((hunks[0].start, hunks[0].end), (4, 8));
+        assert_eq!((hunks[1].old_start, hunks[1].old_count), (10, 1));
+        assert_eq!((hunks[1].new_start, hunks[1].new_count), (10, 2));
+        assert_eq!(hunks[1].file_header, 0..4);
+
+        let patch = hunk_patch(&lines, &hunks[1]);
+        assert!(patch.starts_with("diff --git a/a.txt b/a.txt\n"));
+        assert!(patch.contains("+++ b/a.txt\n@@ -10 +10,2 @@ fn ctx()\n l10\n+l11\n"));
+        assert!(!patch.contains("l2 changed"));
+    }
+
+    #[test]
+    fn test_hunk_patch_for_lines_recomputes_header() {
+        let lines: Vec<String> = [
+            "diff --git a/a.txt b/a.txt",
+            "--- a/a.txt",
+            "+++ b/a.txt",
+            "@@ -4,3 +4,4 @@",
+            " l4",
+            "-l5",
+            "+l5 new",
+            "+l5 extra",
+            " l6",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+        let hunks = parse_diff_hunks(&lines);
+
+        let selected: BTreeSet<usize> = [6].into_iter().collect();
+        let patch = hunk_patch_for_lines(&lines, &hunks[0], &selected, false).unwrap();
+        assert!(patch.contains("@@ -4,3 +4,4 @@\n l4\n l5\n+l5 new\n l6\n"));
+
+        let patch = hunk_patch_for_lines(&lines, &hunks[0], &selected, true).unwrap();
+        assert!(patch.contains("@@ -4,3 +4,4 @@\n l4\n+l5 new\n l5 extra\n l6\n"));
+
+        let selected: BTreeSet<usize> = [4].into_iter().collect();
+        assert!(hunk_patch_for_lines(&lines, &hunks[0], &selected, false).is_none());
+    }
+
     #[test]
     fn test_commit_history_and_detail_with_gix() {
         use std::fs;
@@ -566,6 +677,114 @@ mod tests {
             .iter()
             .any(|l| l.contains("non-blob change (tree/submodule)")));
     }
+
+    #[test]
+    fn test_operation_in_progress_rebase_and_abort() {
+        let env = TestEnv::repo("test_operation_rebase");
+        fs::write(env.root.join("f.txt"), "base\n").unwrap();
+        let _ = system("git add . && git commit -q -m base");
+        let _ = system("git checkout -q -b topic");
+        fs::write(env.root.join("f.txt"), "topic\n").unwrap();
+        let _ = system("git commit -q -am topic");
+        let _ = system("git checkout -q main");
+        fs::write(env.root.join("f.txt"), "main\n").unwrap();
+        let _ = system("git commit -q -am main");
+        let _ = system("git checkout -q topic");
+
+        let root = repo_root().unwrap();
+        assert_eq!(operation_in_progress_at(&root), None);
+        let _ = system("git rebase main");
+        let op = operation_in_progress_at(&root).unwrap();
+        assert_eq!(op.kind, GitOpKind::Rebase);
+        assert_eq!(op.label, "rebase 1/1 onto main");
+        assert!(operation_action_at(&root, &op, GitOpAction::Continue).is_err());
+
+        operation_action_at(&root, &op, GitOpAction::Abort).unwrap();
+        assert_eq!(operation_in_progress_at(&root), None);
+
+        let _ = system("git merge main");
+        let op = operation_in_progress_at(&root).unwrap();
+        assert_eq!(op.label, "merge main");
+        assert_eq!(op.action_args(GitOpAction::Skip), None);
+    }
+
+    #[test]
+    fn test_run_rebase_plan_reorders_rewords_squashes_and_drops() {
+        let env = TestEnv::repo("test_rebase_plan_it's");
+        for name in ["base", "c1", "c2", "c3", "c4"] {
+            fs::write(env.root.join(format!("{}.txt", name)), name).unwrap();
+            let _ = system(&format!("git add . && git commit -q -m {}", name));
+        }
+        let root = repo_root().unwrap();
+        let base = run_git_at("Test", &root, &["rev-parse", "HEAD~4"]).unwrap().trim().to_string();
+        let mut items = rebase_commits_at(&root, &base).unwrap();
+        let subjects: Vec<&str> = items.iter().map(|i| i.subject.as_str()).collect();
+        assert_eq!(subjects, ["c1", "c2", "c3", "c4"]);
+
+        items[0].action = TodoAction::Fixup;
+        assert!(check_rebase_plan(&items).is_err());
+        items[0].action = TodoAction::Reword;
+        items[0].message = Some("c1 reworded\n\nwith body".to_string());
+        items[1].action = TodoAction::Drop;
+        items.swap(2, 3);
+        items[3].action = TodoAction::Fixup;
+        run_rebase_plan_at(&root, &base, &items).unwrap();
+
+        let log = run_git_at("Test", &root, &["log", "--format=%s", &format!("{}..HEAD", base)]).unwrap();
+        assert_eq!(log.lines().collect::<Vec<_>>(), ["c4", "c1 reworded"]);
+        assert_eq!(commit_message_at(&root, "HEAD~1").unwrap(), "c1 reworded\n\nwith body");
+        assert!(!env.root.join("c2.txt").exists());
+        assert!(env.root.join("c3.txt").exists());
+    }
+
+    #[test]
+    fn test_rebase_commits_refuses_merges() {
+        let env = TestEnv::repo("test_rebase_merges");
+        fs::write(env.root.join("a.txt"), "a").unwrap();
+        let _ = system("git add . && git commit -q -m base && git checkout -q -b side");
+        fs::write(env.root.join("b.txt"), "b").unwrap();
+        let _ = system("git add . && git commit -q -m side && git checkout -q main");
+        fs::write(env.root.join("c.txt"), "c").unwrap();
+        let _ = system("git add . && git commit -q -m main && git merge -q --no-edit side");
+        let root = repo_root().unwrap();
+        let base = run_git_at("Test", &root, &["rev-parse", "HEAD~2"]).unwrap().trim().to_string();
+        assert!(rebase_commits_at(&root, &base).is_err());
+        let head = run_git_at("Test", &root, &["rev-parse", "HEAD"]).unwrap().trim().to_string();
+        assert!(rebase_commits_at(&root, &head).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_stash_push_list_and_pop() {
+        let env = TestEnv::repo("test_stash_push_list");
+        fs::write(env.root.join("a.txt"), "a\n").unwrap();
+        fs::write(env.root.join("b.txt"), "b\n").unwrap();
+        let _ = system("git add . && git commit -q -m base");
+        fs::write(env.root.join("a.txt"), "a changed\n").unwrap();
+        fs::write(env.root.join("b.txt"), "b changed\n").unwrap();
+        fs::write(env.root.join("new.txt"), "new\n").unwrap();
+
+        let root = repo_root().unwrap();
+        let only_a = vec![root.join("a.txt").to_string_lossy().to_string()];
+        stash_push_at(&root, "just a", false, &only_a).unwrap();
+        stash_push_at(&root, "", true, &[]).unwrap();
+        assert!(!env.root.join("new.txt").exists());
+
+        let list = stash_list_at(&root).unwrap();
+        assert_eq!(list.len(), 2);
+        assert_eq!(list[1].name, "stash@{1}");
+        assert_eq!(list[1].message, "just a");
+        assert!(!list[1].branch.is_empty());
+        assert!(list[0].message.ends_with("base"));
+
+        let diff = stash_diff_at(&root, "stash@{1}").unwrap();
+        assert!(diff.contains("+a changed"));
+        assert!(!diff.contains("b changed"));
+        assert!(stash_diff_at(&root, "stash@{0}").unwrap().contains("+new"));
+
+        run_git_at("Stash", &root, &["stash", "pop", "stash@{0}"]).unwrap();
+        assert!(env.root.join("new.txt").exists());
+        assert_eq!(stash_list_at(&root).unwrap().len(), 1);
+    }
 }
 
 pub struct StatusEvent {
@@ -819,8 +1038,14 @@ pub fn git_cmd_at(root: &Path, cmd: &str) -> String {
     format!("git -C \"{}\" {}", root.to_string_lossy(), cmd)
 }
 
+// `git status -s` codes of unmerged paths; both columns describe the conflict.
+pub fn is_unmerged(status_code: &str) -> bool {
+    matches!(status_code, "DD" | "AU" | "UD" | "UA" | "DU" | "AA" | "UU")
+}
+
 pub fn build_git_items() -> anyhow::Result<Vec<GitItem>> {
     let list = status_file_list()?;
+    let mut conflicted = Vec::new();
     let mut modified = Vec::new();
     let mut untracked = Vec::new();
     let mut staged = Vec::new();
@@ -829,6 +1054,10 @@ pub fn build_git_items() -> anyhow::Result<Vec<GitItem>> {
             untracked.push((line, "?".to_string()));
             continue;
         }
+        if is_unmerged(&status_code) {
+            conflicted.push((line, "!".to_string()));
+            continue;
+        }
         let bytes = status_code.as_bytes();
         let staged_flag = bytes.get(0).copied().unwrap_or(b' ') != b' ';
         let modified_flag = bytes.get(1).copied().unwrap_or(b' ') != b' ';
@@ -840,6 +1069,22 @@ pub fn build_git_items() -> anyhow::Result<Vec<GitItem>> {
         }
     }
     let mut items = Vec::new();
+    if !conflicted.is_empty() {
+        items.push(GitItem {
+            label: "< Conflicted >".to_string(),
+            status: None,
+            kind: GitItemKind::Header,
+            path: None,
+        });
+        for (clean, status) in conflicted {
+            items.push(GitItem {
+                label: clean.clone(),
+                status: Some(status),
+                kind: GitItemKind::Entry,
+                path: git_file_last_name(&clean),
+            });
+        }
+    }
     if !modified.is_empty() {
         items.push(GitItem {
             label: "< Modified >".to_string(),
@@ -1059,6 +1304,35 @@ pub fn commit_gap(new_branch: &str, old_branch: &str) -> anyhow::Result<usize> {
     Ok(out.trim().parse::<usize>()?)
 }
 
+// Upstream name when HEAD is already contained in it, i.e. amending would rewrite pushed history.
+pub fn head_pushed_to() -> Option<String> {
+    let upstream = get_tracking_branch()?;
+    match commit_gap("HEAD", &upstream) {
+        Ok(0) => Some(upstream),
+        _ => None,
+    }
+}
+
+const EMPTY_TREE: &str = "4b825dc642cb6eb9a060e54bf8d69288fbee4904";
+
+// What an amended HEAD is compared against: HEAD's parent, or the empty tree for a root commit.
+pub fn amend_base_at(root: &Path) -> String {
+    system(&git_cmd_at(root, "rev-parse --verify -q HEAD^")).unwrap_or_else(|_| EMPTY_TREE.to_string())
+}
+
+// Contents of git's `commit.template` with its comment lines removed.
+pub fn commit_template_at(root: &Path) -> Option<String> {
+    let path = system(&git_cmd_at(root, "config --path commit.template")).ok()?;
+    let text = std::fs::read_to_string(root.join(path.trim())).ok()?;
+    let kept: Vec<&str> = text.lines().filter(|l| !l.starts_with('#')).collect();
+    let message = kept.join("\n").trim().to_string();
+    (!message.is_empty()).then_some(message)
+}
+
+pub fn head_message_at(root: &Path) -> anyhow::Result<String> {
+    Ok(system(&git_cmd_at(root, "log -1 --format=%B HEAD"))?)
+}
+
 pub fn commit_log_between(new_branch: &str, old_branch: &str) -> anyhow::Result<String> {
     let cmd = format!(
         "LANG=C git log --color --oneline --graph --decorate --abbrev-commit {}^..{}",
@@ -1175,32 +1449,201 @@ pub fn commit_list() -> anyhow::Result<Vec<String>> {
 }
 
 pub fn commit_history_at(root: &Path, limit: usize) -> anyhow::Result<Vec<CommitSummary>> {
-    let repo = gix::open(root.to_path_buf())?;
-    let head = repo.head_id()?.detach();
-    let walk = repo
-        .rev_walk([head])
-        .sorting(Sorting::ByCommitTime(CommitTimeOrder::NewestFirst))
-        .all()?;
+    HistoryWalk::head_at(root)?.next_page(limit)
+}
+
+// Newest-first rev-walk that can be resumed, so history is read one page at a time.
+// Takes a rev (`main`, `v1.2`) or a range (`origin/main..HEAD`, `v1.2..v1.3`).
+pub struct HistoryWalk {
+    repo: gix::Repository,
+    // (commit time, reversed insertion order) so equal times come out first-in first-out,
+    // which keeps a parent after its children when they share a timestamp.
+    queue: BinaryHeap<(i64, Reverse<usize>, gix::ObjectId)>,
+    seen: HashSet<gix::ObjectId>,
+    // Reachable from the left side of a range; walked only to mark their parents.
+    hidden: HashSet<gix::ObjectId>,
+}
+
+impl HistoryWalk {
+    pub fn head_at(root: &Path) -> anyhow::Result<Self> {
+        Self::rev_at(root, "HEAD")
+    }
+
+    pub fn rev_at(root: &Path, rev: &str) -> anyhow::Result<Self> {
+        let repo = gix::open(root.to_path_buf())?;
+        let (hide, show) = match rev.split_once("..") {
+            Some((from, to)) => (Some(from), to),
+            None => (None, rev),
+        };
+        let resolve = |name: &str| -> anyhow::Result<gix::ObjectId> {
+            let name = if name.is_empty() { "HEAD" } else { name };
+            let id = repo
+                .rev_parse_single(name.as_bytes().as_bstr())
+                .map_err(|_| anyhow::anyhow!("Unknown revision: {}", name))?;
+            Ok(id.object()?.peel_to_commit()?.id)
+        };
+        let show = resolve(show)?;
+        let hide = hide.map(resolve).transpose()?;
+        let mut walk = Self {
+            repo,
+            queue: BinaryHeap::new(),
+            seen: HashSet::new(),
+            hidden: HashSet::new(),
+        };
+        if let Some(hide) = hide {
+            walk.push(hide, true);
+        }
+        walk.push(show, false);
+        Ok(walk)
+    }
+
+    // Parents missing from a shallow clone just end the walk there.
+    fn push(&mut self, id: gix::ObjectId, hidden: bool) {
+        if hidden {
+            self.hidden.insert(id);
+        }
+        if !self.seen.insert(id) {
+            return;
+        }
+        if let Some(time) = self.repo.find_commit(id).ok().and_then(|c| c.time().ok()) {
+            self.queue.push((time.seconds, Reverse(self.seen.len()), id));
+        }
+    }
+
+    // Done once only hidden commits are left to walk.
+    pub fn is_done(&self) -> bool {
+        self.queue.iter().all(|(_, _, id)| self.hidden.contains(id))
+    }
 
+    // Commit time of the next commit to come out; later pages never get newer than this.
+    pub fn peek_time(&self) -> Option<i64> {
+        self.queue.peek().map(|(time, _, _)| *time)
+    }
+
+    pub fn next_page(&mut self, limit: usize) -> anyhow::Result<Vec<CommitSummary>> {
+        let mut out = Vec::new();
+        while out.len() < limit && !self.is_done() {
+            let Some((_, _, id)) = self.queue.pop() else {
+                break;
+            };
+            let hidden = self.hidden.contains(&id);
+            let parents: Vec<gix::ObjectId> = {
+                let commit = self.repo.find_commit(id)?;
+                let parents: Vec<gix::ObjectId> = commit.parent_ids().map(|p| p.detach()).collect();
+                if !hidden {
+                    let listed: Vec<String> = parents
+                        .iter()
+                        .filter(|p| !self.hidden.contains(*p) && self.repo.has_object(*p))
+                        .map(|p| p.to_string())
+                        .collect();
+                    let signature = commit.author()?;
+                    let time = commit.time()?;
+                    let message = commit.message_raw_sloppy().to_str_lossy().to_string();
+                    out.push(CommitSummary {
+                        hash: id.to_string(),
+                        author: signature.name.to_str_lossy().to_string(),
+                        email: signature.email.to_str_lossy().to_string(),
+                        date: format_datetime(time.seconds, time.offset),
+                        time: time.seconds,
+                        offset: time.offset,
+                        subject: first_line(&message),
+                        message,
+                        parents: listed,
+                        merge: parents.len() > 1,
+                    });
+                }
+                parents
+            };
+            for parent in parents {
+                self.push(parent, hidden);
+            }
+        }
+        Ok(out)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RefKind {
+    Head,
+    Local,
+    Remote,
+    Tag,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RefLabel {
+    pub kind: RefKind,
+    pub name: String,
+}
+
+// Local branches, remote branches and tags with the commit each points at.
+fn refs_of(repo: &gix::Repository) -> anyhow::Result<Vec<(RefLabel, gix::ObjectId)>> {
     let mut out = Vec::new();
-    for info in walk.take(limit) {
-        let info = info?;
-        let commit = info.object()?;
-        let author = commit.author()?.name.to_str_lossy().to_string();
-        let subject = first_line(&commit.message_raw_sloppy().to_str_lossy());
-        let date = commit.time()?.seconds.to_string();
-        let hash = short_hash(&info.id.to_string());
-
-        out.push(CommitSummary {
-            hash,
-            author,
-            date,
-            subject,
-        });
+    let platform = repo.references()?;
+    for reference in platform.all()? {
+        let Ok(mut reference) = reference else {
+            continue;
+        };
+        let full = reference.name().as_bstr().to_string();
+        let label = if let Some(name) = full.strip_prefix("refs/heads/") {
+            RefLabel { kind: RefKind::Local, name: name.to_string() }
+        } else if let Some(name) = full.strip_prefix("refs/remotes/") {
+            if name.ends_with("/HEAD") {
+                continue;
+            }
+            RefLabel { kind: RefKind::Remote, name: name.to_string() }
+        } else if let Some(name) = full.strip_prefix("refs/tags/") {
+            RefLabel { kind: RefKind::Tag, name: name.to_string() }
+        } else {
+            continue;
+        };
+        if let Ok(id) = reference.peel_to_id_in_place() {
+            out.push((label, id.detach()));
+        }
     }
     Ok(out)
 }
 
+// Branches, remote branches, tags and HEAD keyed by the full hash of the commit they point at.
+// An attached HEAD is folded into its branch as "HEAD -> main".
+pub fn ref_labels_at(root: &Path) -> anyhow::Result<HashMap<String, Vec<RefLabel>>> {
+    let repo = gix::open(root.to_path_buf())?;
+    let head_branch = repo.head_name()?.map(|n| n.shorten().to_string());
+    let mut out: HashMap<String, Vec<RefLabel>> = HashMap::new();
+    if head_branch.is_none() {
+        if let Ok(id) = repo.head_id() {
+            out.entry(id.to_string()).or_default().push(RefLabel {
+                kind: RefKind::Head,
+                name: "HEAD".to_string(),
+            });
+        }
+    }
+    for (mut label, id) in refs_of(&repo)? {
+        if label.kind == RefKind::Local && head_branch.as_deref() == Some(label.name.as_str()) {
+            label = RefLabel { kind: RefKind::Head, name: format!("HEAD -> {}", label.name) };
+        }
+        let labels = out.entry(id.to_string()).or_default();
+        labels.push(label);
+        labels.sort_by_key(|l| l.kind as u8);
+    }
+    Ok(out)
+}
+
+// Everything GitHistory can browse: HEAD, then local branches, remote branches and tags.
+pub fn ref_list_at(root: &Path) -> anyhow::Result<Vec<RefLabel>> {
+    let repo = gix::open(root.to_path_buf())?;
+    let mut refs: Vec<RefLabel> = refs_of(&repo)?.into_iter().map(|(label, _)| label).collect();
+    refs.sort_by(|a, b| (a.kind as u8, &a.name).cmp(&(b.kind as u8, &b.name)));
+    refs.insert(0, RefLabel { kind: RefKind::Head, name: "HEAD".to_string() });
+    Ok(refs)
+}
+
+// Number of commits in `rev` (a rev or range); `rev-list` can use the commit-graph, so this stays cheap.
+pub fn commit_count_at(root: &Path, rev: &str) -> anyhow::Result<usize> {
+    let out = run_git_at("History", root, &["rev-list", "--count", rev])?;
+    Ok(out.trim().parse()?)
+}
+
 pub fn commit_detail_at(root: &Path, hash: &str) -> anyhow::Result<Vec<String>> {
     let repo = gix::open(root.to_path_buf())?;
     let id = repo.rev_parse_single(hash.as_bytes().as_bstr())?;
@@ -1224,7 +1667,12 @@ pub fn commit_detail_at(root: &Path, hash: &str) -> anyhow::Result<Vec<String>>
     let mut lines = Vec::new();
     lines.push(format!("commit {}", commit_id));
     lines.push(format!("Author: {} <{}>", author_name, author_email));
-    lines.push(format!("Date:   {} {}", time.seconds, time.offset));
+    lines.push(format!(
+        "Date:   {} {} ({})",
+        format_datetime(time.seconds, time.offset),
+        format_offset(time.offset),
+        format_relative(time.seconds, now_secs())
+    ));
     lines.push(String::new());
     for msg_line in message.lines() {
         lines.push(format!("    {}", msg_line));
@@ -1237,6 +1685,27 @@ pub fn commit_detail_at(root: &Path, hash: &str) -> anyhow::Result<Vec<String>>
     Ok(lines)
 }
 
+// Tree-to-tree diff from `from` to `to`, e.g. everything between two deploys.
+pub fn compare_commits_at(root: &Path, from: &str, to: &str) -> anyhow::Result<Vec<String>> {
+    let repo = gix::open(root.to_path_buf())?;
+    let from = repo.rev_parse_single(from.as_bytes().as_bstr())?.object()?.into_commit();
+    let to = repo.rev_parse_single(to.as_bytes().as_bstr())?.object()?.into_commit();
+    let changes = repo.diff_tree_to_tree(Some(&from.tree()?), Some(&to.tree()?), None)?;
+
+    let mut lines = Vec::new();
+    lines.push(format!("compare {}..{}", from.id, to.id));
+    for (label, commit) in [("From:", &from), ("To:", &to)] {
+        let subject = commit.message_raw_sloppy().to_str_lossy().lines().next().unwrap_or("").to_string();
+        lines.push(format!("{:<7} {} {}", label, short_hash(&commit.id.to_string()), subject));
+    }
+    lines.push(String::new());
+    lines.push(format!("Files changed: {}", changes.len()));
+    for ch in &changes {
+        append_patch_for_change(&repo, &mut lines, ch)?;
+    }
+    Ok(lines)
+}
+
 pub fn status_file_list() -> anyhow::Result<Vec<(String, String)>> {
     // Get status without color for reliable parsing
     let out = system_logged("GitStage", "LANG=C git status -s")?;
@@ -1261,3 +1730,611 @@ pub fn add_to_gitignore(path: &str) -> anyhow::Result<()> {
     writeln!(file, "{}", path)?;
     Ok(())
 }
+
+pub fn file_diff(name: &str, staged: bool) -> anyhow::Result<String> {
+    // Keep the raw output (tabs, CR, trailing blanks) so hunks can be applied back.
+    let mut cmd = std::process::Command::new("git");
+    cmd.arg("diff").arg("--no-color");
+    if staged {
+        cmd.arg("--staged");
+    }
+    let out = cmd.arg("--").arg(name).env("LANG", "C").output()?;
+    if !out.status.success() {
+        return Err(anyhow::anyhow!(
+            "{}",
+            String::from_utf8_lossy(&out.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&out.stdout).to_string())
+}
+
+pub fn parse_hunk_header(line: &str) -> Option<(usize, usize, usize, usize)> {
+    let re = Regex::new(r"^@@ -(\d+)(?:,(\d+))? \+(\d+)(?:,(\d+))? @@").ok()?;
+    let caps = re.captures(line)?;
+    let num = |i: usize, default: usize| {
+        caps.get(i)
+            .and_then(|m| m.as_str().parse::<usize>().ok())
+            .unwrap_or(default)
+    };
+    Some((num(1, 0), num(2, 1), num(3, 0), num(4, 1)))
+}
+
+pub fn parse_diff_hunks(lines: &[String]) -> Vec<DiffHunk> {
+    let mut hunks: Vec<DiffHunk> = Vec::new();
+    let mut file_start: Option<usize> = None;
+    let mut file_header: Option<Range<usize>> = None;
+    let mut open = false;
+    for (i, line) in lines.iter().enumerate() {
+        if line.starts_with("diff --git") {
+            file_start = Some(i);
+            file_header = None;
+            open = false;
+            continue;
+        }
+        if line.starts_with("@@") {
+            let Some(start) = file_start else { continue };
+            let header = file_header.get_or_insert(start..i).clone();
+            if let Some((old_start, old_count, new_start, new_count)) = parse_hunk_header(line) {
+                hunks.push(DiffHunk {
+                    old_start,
+                    old_count,
+                    new_start,
+                    new_count,
+                    start: i,
+                    end: i + 1,
+                    file_header: header,
+                });
+                open = true;
+            }
+            continue;
+        }
+        if open && (line.starts_with(' ') || line.starts_with('+') || line.starts_with('-') || line.starts_with('\\')) {
+            if let Some(last) = hunks.last_mut() {
+                last.end = i + 1;
+            }
+        } else {
+            open = false;
+        }
+    }
+    hunks
+}
+
+pub fn hunk_patch(lines: &[String], hunk: &DiffHunk) -> String {
+    let mut patch = String::new();
+    for line in &lines[hunk.file_header.clone()] {
+        patch.push_str(line);
+        patch.push('\n');
+    }
+    for line in &lines[hunk.start..hunk.end] {
+        patch.push_str(line);
+        patch.push('\n');
+    }
+    patch
+}
+
+// Builds a patch holding only the `selected` +/- lines of `hunk`.
+// Unselected changes are turned into context on the side the patch is applied to
+// (the old side, or the new side when `reverse`) and dropped on the other.
+pub fn hunk_patch_for_lines(
+    lines: &[String],
+    hunk: &DiffHunk,
+    selected: &BTreeSet<usize>,
+    reverse: bool,
+) -> Option<String> {
+    let mut body = Vec::new();
+    let mut old_count = 0;
+    let mut new_count = 0;
+    let mut changed = false;
+    let mut kept_prev = false;
+    for (i, line) in lines.iter().enumerate().take(hunk.end).skip(hunk.start + 1) {
+        let rest = line.get(1..).unwrap_or("");
+        let kept = if line.starts_with('+') || line.starts_with('-') {
+            let is_add = line.starts_with('+');
+            if selected.contains(&i) {
+                body.push(line.clone());
+                if is_add { new_count += 1 } else { old_count += 1 }
+                changed = true;
+                true
+            } else if is_add == reverse {
+                body.push(format!(" {}", rest));
+                old_count += 1;
+                new_count += 1;
+                true
+            } else {
+                false
+            }
+        } else if line.starts_with('\\') {
+            if kept_prev {
+                body.push(line.clone());
+            }
+            kept_prev
+        } else {
+            body.push(line.clone());
+            old_count += 1;
+            new_count += 1;
+            true
+        };
+        kept_prev = kept;
+    }
+    if !changed {
+        return None;
+    }
+
+    let old_start = if hunk.old_count == 0 { hunk.old_start } else { hunk.old_start.saturating_sub(1) };
+    let new_start = if hunk.new_count == 0 { hunk.new_start } else { hunk.new_start.saturating_sub(1) };
+    let mut patch = String::new();
+    for line in &lines[hunk.file_header.clone()] {
+        patch.push_str(line);
+        patch.push('\n');
+    }
+    patch.push_str(&format!(
+        "@@ -{},{} +{},{} @@\n",
+        hunk_header_pos(old_start, old_count),
+        old_count,
+        hunk_header_pos(new_start, new_count),
+        new_count
+    ));
+    for line in body {
+        patch.push_str(&line);
+        patch.push('\n');
+    }
+    Some(patch)
+}
+
+// Runs `git -C root <args>` without a shell; the error carries git's stderr.
+pub fn run_git_at(context: &str, root: &Path, args: &[&str]) -> anyhow::Result<String> {
+    let out = std::process::Command::new("git")
+        .arg("-C")
+        .arg(root)
+        .args(args)
+        .env("LANG", "C")
+        // There is no terminal to edit in; continue/merge keep the prepared message.
+        .env("GIT_EDITOR", "true")
+        .stdin(std::process::Stdio::null())
+        .output()?;
+    let stdout = String::from_utf8_lossy(&out.stdout).to_string();
+    let result = if out.status.success() {
+        Ok(stdout)
+    } else {
+        let stderr = String::from_utf8_lossy(&out.stderr);
+        let text = if stderr.trim().is_empty() { stdout } else { stderr.to_string() };
+        Err(std::io::Error::other(text.trim().to_string()))
+    };
+    log_command_result(context, &format!("git {}", args.join(" ")), &result);
+    result.map_err(Into::into)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GitOpKind {
+    Rebase,
+    Merge,
+    CherryPick,
+    Revert,
+    Bisect,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GitOpAction {
+    Continue,
+    Skip,
+    Abort,
+}
+
+// A rebase, merge, cherry-pick, revert or bisect that is waiting for the user.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct GitOperation {
+    pub kind: GitOpKind,
+    // e.g. "rebase 3/7 onto origin/main"
+    pub label: String,
+}
+
+impl GitOpKind {
+    pub fn command(self) -> &'static str {
+        match self {
+            GitOpKind::Rebase => "rebase",
+            GitOpKind::Merge => "merge",
+            GitOpKind::CherryPick => "cherry-pick",
+            GitOpKind::Revert => "revert",
+            GitOpKind::Bisect => "bisect",
+        }
+    }
+}
+
+impl GitOperation {
+    pub fn command(&self) -> &'static str {
+        self.kind.command()
+    }
+
+    // Git arguments for `action`, or None when the operation has no such step.
+    pub fn action_args(&self, action: GitOpAction) -> Option<Vec<&'static str>> {
+        let cmd = self.command();
+        match (self.kind, action) {
+            (GitOpKind::Bisect, GitOpAction::Continue) => None,
+            (GitOpKind::Bisect, GitOpAction::Skip) => Some(vec![cmd, "skip"]),
+            (GitOpKind::Bisect, GitOpAction::Abort) => Some(vec![cmd, "reset"]),
+            (GitOpKind::Merge, GitOpAction::Skip) => None,
+            (_, GitOpAction::Continue) => Some(vec![cmd, "--continue"]),
+            (_, GitOpAction::Skip) => Some(vec![cmd, "--skip"]),
+            (_, GitOpAction::Abort) => Some(vec![cmd, "--abort"]),
+        }
+    }
+}
+
+fn read_trimmed(path: &Path) -> Option<String> {
+    std::fs::read_to_string(path).ok().map(|s| s.trim().to_string())
+}
+
+// A branch or tag pointing at `rev`, falling back to the short hash.
+fn ref_name_at(root: &Path, rev: &str) -> String {
+    let out = run_git_at(
+        "Operation",
+        root,
+        &["for-each-ref", "--points-at", rev, "--format=%(refname:short)", "refs/heads", "refs/remotes", "refs/tags"],
+    )
+    .unwrap_or_default();
+    match out.lines().next() {
+        Some(name) => name.to_string(),
+        None => rev.chars().take(7).collect(),
+    }
+}
+
+// Remaining picks of a multi-commit cherry-pick/revert.
+fn sequencer_left(git_dir: &Path) -> usize {
+    std::fs::read_to_string(git_dir.join("sequencer/todo"))
+        .map(|t| t.lines().filter(|l| !l.trim().is_empty() && !l.starts_with('#')).count())
+        .unwrap_or(0)
+}
+
+pub fn operation_in_progress_at(root: &Path) -> Option<GitOperation> {
+    let git_dir = PathBuf::from(run_git_at("Operation", root, &["rev-parse", "--absolute-git-dir"]).ok()?.trim());
+    for dir in ["rebase-merge", "rebase-apply"] {
+        let dir = git_dir.join(dir);
+        if !dir.is_dir() {
+            continue;
+        }
+        let (step, total) = if dir.ends_with("rebase-merge") { ("msgnum", "end") } else { ("next", "last") };
+        let mut label = "rebase".to_string();
+        if let (Some(step), Some(total)) = (read_trimmed(&dir.join(step)), read_trimmed(&dir.join(total))) {
+            label.push_str(&format!(" {}/{}", step, total));
+        }
+        if let Some(onto) = read_trimmed(&dir.join("onto")) {
+            label.push_str(&format!(" onto {}", ref_name_at(root, &onto)));
+        }
+        return Some(GitOperation { kind: GitOpKind::Rebase, label });
+    }
+    let heads = [
+        ("MERGE_HEAD", GitOpKind::Merge),
+        ("CHERRY_PICK_HEAD", GitOpKind::CherryPick),
+        ("REVERT_HEAD", GitOpKind::Revert),
+    ];
+    for (file, kind) in heads {
+        let Some(head) = read_trimmed(&git_dir.join(file)) else {
+            continue;
+        };
+        let rev = head.lines().next().unwrap_or("");
+        let mut label = format!("{} {}", kind.command(), ref_name_at(root, rev));
+        let left = sequencer_left(&git_dir);
+        if left > 1 {
+            label.push_str(&format!(" ({} left)", left));
+        }
+        return Some(GitOperation { kind, label });
+    }
+    if git_dir.join("BISECT_LOG").exists() {
+        let mut label = "bisect".to_string();
+        if let Some(start) = read_trimmed(&git_dir.join("BISECT_START")) {
+            label.push_str(&format!(" (started on {})", start));
+        }
+        return Some(GitOperation { kind: GitOpKind::Bisect, label });
+    }
+    None
+}
+
+pub fn operation_action_at(root: &Path, op: &GitOperation, action: GitOpAction) -> anyhow::Result<String> {
+    let Some(args) = op.action_args(action) else {
+        return Err(anyhow::anyhow!("{} has no {:?} step", op.command(), action));
+    };
+    run_git_at("Operation", root, &args)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TodoAction {
+    Pick,
+    Reword,
+    Squash,
+    Fixup,
+    Drop,
+}
+
+impl TodoAction {
+    pub fn word(self) -> &'static str {
+        match self {
+            TodoAction::Pick => "pick",
+            TodoAction::Reword => "reword",
+            TodoAction::Squash => "squash",
+            TodoAction::Fixup => "fixup",
+            TodoAction::Drop => "drop",
+        }
+    }
+}
+
+// One line of an interactive rebase plan.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TodoItem {
+    pub action: TodoAction,
+    pub hash: String,
+    pub subject: String,
+    // New message for `Reword`.
+    pub message: Option<String>,
+}
+
+// Commits `git rebase -i base` would replay, oldest first. A plain todo list would flatten
+// merges, so ranges with any are refused.
+pub fn rebase_commits_at(root: &Path, base: &str) -> anyhow::Result<Vec<TodoItem>> {
+    let range = format!("{}..HEAD", base);
+    let merges = run_git_at("Rebase", root, &["rev-list", "--merges", "--count", &range])?;
+    if merges.trim() != "0" {
+        return Err(anyhow::anyhow!("{} merge commit(s) above {}; cannot plan a rebase over merges", merges.trim(), &base[..7.min(base.len())]));
+    }
+    let out = run_git_at("Rebase", root, &["log", "--reverse", "--topo-order", "--format=%H%x1f%s", &range])?;
+    Ok(out
+        .lines()
+        .filter_map(|l| l.split_once('\x1f'))
+        .map(|(hash, subject)| TodoItem {
+            action: TodoAction::Pick,
+            hash: hash.to_string(),
+            subject: subject.to_string(),
+            message: None,
+        })
+        .collect())
+}
+
+pub fn commit_message_at(root: &Path, rev: &str) -> anyhow::Result<String> {
+    Ok(run_git_at("Rebase", root, &["log", "-1", "--format=%B", rev])?.trim_end().to_string())
+}
+
+// (full hash, subject) of `git log <args>`, newest first.
+pub fn log_subjects_at(root: &Path, args: &[&str]) -> anyhow::Result<Vec<(String, String)>> {
+    let mut full = vec!["log", "--format=%H%x1f%s"];
+    full.extend_from_slice(args);
+    let out = run_git_at("Log", root, &full)?;
+    Ok(out
+        .lines()
+        .filter_map(|l| l.split_once('\x1f'))
+        .map(|(h, s)| (h.to_string(), s.to_string()))
+        .collect())
+}
+
+pub fn fixup_commit_at(root: &Path, target: &str) -> anyhow::Result<String> {
+    run_git_at("Fixup", root, &["commit", "-q", &format!("--fixup={}", target)])
+}
+
+pub fn is_ancestor_at(root: &Path, ancestor: &str, rev: &str) -> bool {
+    run_git_at("Log", root, &["merge-base", "--is-ancestor", ancestor, rev]).is_ok()
+}
+
+// Folds pending `fixup!` commits into their targets, from `target`'s parent (or the root) up.
+// Merges in between are recreated rather than flattened.
+pub fn autosquash_at(root: &Path, target: &str) -> anyhow::Result<String> {
+    let parent = format!("{}^", target);
+    let base = match run_git_at("Fixup", root, &["rev-parse", "--verify", "-q", &parent]) {
+        Ok(_) => parent,
+        Err(_) => "--root".to_string(),
+    };
+    run_git_at("Fixup", root, &["-c", "sequence.editor=true", "rebase", "-i", "--autosquash", "--autostash", "--rebase-merges", &base])
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BlameLine {
+    // Full hash; all zeros for lines not committed yet.
+    pub hash: String,
+    pub author: String,
+    pub time: i64,
+    pub summary: String,
+    // Line number in the blamed version of the file, from 1.
+    pub line_no: usize,
+    pub text: String,
+    // (parent hash, path there) to blame next when digging past this commit.
+    pub previous: Option<(String, String)>,
+}
+
+// Parses `git blame --porcelain`; commit details only follow the first line of each commit.
+pub fn parse_blame_porcelain(out: &str) -> Vec<BlameLine> {
+    let mut commits: HashMap<String, BlameLine> = HashMap::new();
+    let mut lines = Vec::new();
+    let mut cur: Option<BlameLine> = None;
+    for line in out.lines() {
+        if let Some(text) = line.strip_prefix('\t') {
+            if let Some(mut done) = cur.take() {
+                done.text = text.to_string();
+                let info = commits.entry(done.hash.clone()).or_insert_with(|| done.clone());
+                done.author = info.author.clone();
+                done.time = info.time;
+                done.summary = info.summary.clone();
+                done.previous = info.previous.clone();
+                lines.push(done);
+            }
+            continue;
+        }
+        let Some(entry) = cur.as_mut() else {
+            let mut parts = line.split(' ');
+            let hash = parts.next().unwrap_or("").to_string();
+            let line_no = parts.nth(1).and_then(|n| n.parse().ok()).unwrap_or(0);
+            cur = Some(BlameLine { hash, line_no, ..Default::default() });
+            continue;
+        };
+        let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+        match key {
+            "author" => entry.author = value.to_string(),
+            "author-time" => entry.time = value.parse().unwrap_or(0),
+            "summary" => entry.summary = value.to_string(),
+            "previous" => {
+                entry.previous = value.split_once(' ').map(|(h, p)| (h.to_string(), p.to_string()));
+            }
+            _ => {}
+        }
+    }
+    lines
+}
+
+// Blames `path` (relative to `root`) at `rev`, or the working tree when `rev` is None.
+pub fn blame_at(root: &Path, rev: Option<&str>, path: &str) -> anyhow::Result<Vec<BlameLine>> {
+    let mut args = vec!["blame", "--porcelain"];
+    if let Some(rev) = rev {
+        args.push(rev);
+    }
+    args.extend(["--", path]);
+    Ok(parse_blame_porcelain(&run_git_at("Blame", root, &args)?))
+}
+
+// A squash or fixup needs an earlier commit in the plan to fold into.
+pub fn check_rebase_plan(items: &[TodoItem]) -> anyhow::Result<()> {
+    let first = items.iter().find(|i| i.action != TodoAction::Drop);
+    if let Some(item) = first.filter(|i| matches!(i.action, TodoAction::Squash | TodoAction::Fixup)) {
+        return Err(anyhow::anyhow!("{} {} has no earlier commit to fold into", item.action.word(), &item.hash[..7.min(item.hash.len())]));
+    }
+    Ok(())
+}
+
+// Single-quoted for `sh`, which git uses for the sequence editor and `exec` lines.
+fn shell_quote(text: &str) -> String {
+    format!("'{}'", text.replace('\'', "'\\''"))
+}
+
+// Runs `git rebase -i` with `items` as the todo list instead of opening an editor.
+// Rewords become a pick plus `commit --amend -F`, so no message editor is needed either;
+// the message files live in the git dir so they survive a stop for conflicts.
+pub fn run_rebase_plan_at(root: &Path, base: &str, items: &[TodoItem]) -> anyhow::Result<String> {
+    check_rebase_plan(items)?;
+    let git_dir = PathBuf::from(run_git_at("Rebase", root, &["rev-parse", "--absolute-git-dir"])?.trim());
+    let dir = git_dir.join("sc-rebase");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir)?;
+    let mut todo = String::new();
+    for (i, item) in items.iter().enumerate() {
+        match (item.action, &item.message) {
+            (TodoAction::Reword, Some(message)) => {
+                let file = dir.join(format!("msg-{}", i));
+                std::fs::write(&file, message)?;
+                todo.push_str(&format!("pick {} {}\n", item.hash, item.subject));
+                todo.push_str(&format!(
+                    "exec git commit --amend --allow-empty --no-verify -q -F {}\n",
+                    shell_quote(&file.to_string_lossy())
+                ));
+            }
+            (TodoAction::Reword, None) => todo.push_str(&format!("pick {} {}\n", item.hash, item.subject)),
+            (action, _) => todo.push_str(&format!("{} {} {}\n", action.word(), item.hash, item.subject)),
+        }
+    }
+    let todo_path = dir.join("todo");
+    std::fs::write(&todo_path, todo)?;
+    let editor = format!("sequence.editor=cp {}", shell_quote(&todo_path.to_string_lossy()));
+    run_git_at("Rebase", root, &["-c", &editor, "rebase", "-i", "--autostash", base])
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct StashEntry {
+    // `stash@{N}`
+    pub name: String,
+    pub branch: String,
+    pub message: String,
+    pub time: u64,
+}
+
+pub fn stash_list_at(root: &Path) -> anyhow::Result<Vec<StashEntry>> {
+    let out = run_git_at("Stash", root, &["stash", "list", "--format=%gd%x1f%ct%x1f%gs"])?;
+    let mut list = Vec::new();
+    for line in out.lines() {
+        let mut parts = line.splitn(3, '\x1f');
+        let (Some(name), Some(time), Some(subject)) = (parts.next(), parts.next(), parts.next()) else {
+            continue;
+        };
+        // "WIP on main: 1a2b3c4 subject" for a plain stash, "On main: message" with -m.
+        let rest = subject
+            .strip_prefix("WIP on ")
+            .or_else(|| subject.strip_prefix("On "))
+            .unwrap_or(subject);
+        let (branch, message) = rest.split_once(": ").unwrap_or(("", rest));
+        list.push(StashEntry {
+            name: name.to_string(),
+            branch: branch.to_string(),
+            message: message.to_string(),
+            time: time.parse().unwrap_or(0),
+        });
+    }
+    Ok(list)
+}
+
+pub fn stash_diff_at(root: &Path, name: &str) -> anyhow::Result<String> {
+    run_git_at("Stash", root, &["stash", "show", "-p", "--no-color", "--include-untracked", name])
+        .or_else(|_| run_git_at("Stash", root, &["stash", "show", "-p", "--no-color", name]))
+}
+
+// Unmerged paths, relative to the repo root.
+pub fn conflicted_files_at(root: &Path) -> anyhow::Result<Vec<String>> {
+    let out = run_git_at("Conflict", root, &["diff", "--name-only", "--diff-filter=U"])?;
+    Ok(out.lines().filter(|l| !l.is_empty()).map(|l| l.to_string()).collect())
+}
+
+// Index stage of an unmerged path: 1 base, 2 ours, 3 theirs. None when that side has no file.
+pub fn conflict_stage_at(root: &Path, stage: u8, path: &str) -> Option<String> {
+    let out = std::process::Command::new("git")
+        .arg("-C")
+        .arg(root)
+        .args(["show", &format!(":{}:{}", stage, path)])
+        .stdin(std::process::Stdio::null())
+        .output()
+        .ok()?;
+    out.status.success().then(|| String::from_utf8_lossy(&out.stdout).to_string())
+}
+
+// `paths` limits the stash to those files; empty stashes everything.
+pub fn stash_push_at(root: &Path, message: &str, include_untracked: bool, paths: &[String]) -> anyhow::Result<String> {
+    let mut args = vec!["stash", "push"];
+    if include_untracked {
+        args.push("--include-untracked");
+    }
+    if !message.trim().is_empty() {
+        args.push("-m");
+        args.push(message);
+    }
+    if !paths.is_empty() {
+        args.push("--");
+        args.extend(paths.iter().map(|p| p.as_str()));
+    }
+    run_git_at("Stash", root, &args)
+}
+
+pub fn apply_patch_at(root: &Path, patch: &str, cached: bool, reverse: bool) -> anyhow::Result<()> {
+    let mut cmd = std::process::Command::new("git");
+    cmd.arg("-C").arg(root).arg("apply").arg("--whitespace=nowarn");
+    if cached {
+        cmd.arg("--cached");
+    }
+    if reverse {
+        cmd.arg("-R");
+    }
+    let mut child = cmd
+        .arg("-")
+        .env("LANG", "C")
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(patch.as_bytes())?;
+    }
+    let out = child.wait_with_output()?;
+    let result = if out.status.success() {
+        Ok(String::from_utf8_lossy(&out.stdout).to_string())
+    } else {
+        Err(std::io::Error::other(
+            String::from_utf8_lossy(&out.stderr).trim().to_string(),
+        ))
+    };
+    log_command_result(
+        "GitStage",
+        &format!("git apply cached={} reverse={}", cached, reverse),
+        &result,
+    );
+    result.map(|_| ()).map_err(Into::into)
+}
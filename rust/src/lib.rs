@@ -2,5 +2,6 @@ pub mod app;
 pub mod config;
 pub mod git;
 pub mod system;
+pub mod trash;
 pub mod util;
 pub mod ui;
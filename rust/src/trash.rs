@@ -0,0 +1,224 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use crate::git;
+
+// Discards older than the newest KEEP entries are dropped when a new one is saved.
+const KEEP: usize = 100;
+const META: &str = "meta.json";
+const DATA: &str = "data";
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub enum TrashKind {
+    File,
+    Dir,
+    // A patch that was reverse-applied to the worktree; restoring applies it again.
+    Patch,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct TrashMeta {
+    pub repo: String,
+    // Absolute path of the discarded file, or of the file the patch belongs to.
+    pub path: String,
+    pub kind: TrashKind,
+    pub time: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct TrashEntry {
+    pub dir: PathBuf,
+    pub meta: TrashMeta,
+}
+
+impl TrashEntry {
+    pub fn data_path(&self) -> PathBuf {
+        self.dir.join(DATA)
+    }
+
+    pub fn display_path(&self) -> String {
+        Path::new(&self.meta.path)
+            .strip_prefix(&self.meta.repo)
+            .map(|p| p.to_string_lossy().to_string())
+            .unwrap_or_else(|_| self.meta.path.clone())
+    }
+}
+
+pub fn trash_root() -> PathBuf {
+    let home = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
+    Path::new(&home).join(".synapcmd").join("trash")
+}
+
+fn now_nanos() -> u128 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_nanos()).unwrap_or(0)
+}
+
+fn copy_recursive(src: &Path, dst: &Path) -> std::io::Result<()> {
+    if src.is_dir() {
+        fs::create_dir_all(dst)?;
+        for entry in fs::read_dir(src)? {
+            let entry = entry?;
+            copy_recursive(&entry.path(), &dst.join(entry.file_name()))?;
+        }
+        Ok(())
+    } else {
+        fs::copy(src, dst).map(|_| ())
+    }
+}
+
+fn new_entry(root: &Path, repo: &Path, path: &Path, kind: TrashKind) -> anyhow::Result<TrashEntry> {
+    let nanos = now_nanos();
+    let dir = root.join(nanos.to_string());
+    fs::create_dir_all(&dir)?;
+    let meta = TrashMeta {
+        repo: repo.to_string_lossy().to_string(),
+        path: path.to_string_lossy().to_string(),
+        kind,
+        time: (nanos / 1_000_000_000) as u64,
+    };
+    fs::write(dir.join(META), serde_json::to_string_pretty(&meta)?)?;
+    Ok(TrashEntry { dir, meta })
+}
+
+// Copies a file or directory into the trash before it is discarded.
+// Returns None when there is nothing on disk to keep (e.g. a deleted file).
+pub fn save_path(root: &Path, repo: &Path, path: &Path) -> anyhow::Result<Option<TrashEntry>> {
+    if fs::symlink_metadata(path).is_err() {
+        return Ok(None);
+    }
+    let kind = if path.is_dir() { TrashKind::Dir } else { TrashKind::File };
+    let entry = new_entry(root, repo, path, kind)?;
+    copy_recursive(path, &entry.data_path())?;
+    prune(root)?;
+    Ok(Some(entry))
+}
+
+pub fn save_patch(root: &Path, repo: &Path, path: &Path, patch: &str) -> anyhow::Result<TrashEntry> {
+    let entry = new_entry(root, repo, path, TrashKind::Patch)?;
+    fs::write(entry.data_path(), patch)?;
+    prune(root)?;
+    Ok(entry)
+}
+
+// Newest first; unreadable entries are skipped.
+pub fn list(root: &Path) -> anyhow::Result<Vec<TrashEntry>> {
+    let mut out = Vec::new();
+    let Ok(dirs) = fs::read_dir(root) else {
+        return Ok(out);
+    };
+    for dir in dirs.flatten() {
+        let dir = dir.path();
+        let Ok(text) = fs::read_to_string(dir.join(META)) else {
+            continue;
+        };
+        if let Ok(meta) = serde_json::from_str::<TrashMeta>(&text) {
+            out.push(TrashEntry { dir, meta });
+        }
+    }
+    out.sort_by(|a, b| b.dir.file_name().cmp(&a.dir.file_name()));
+    Ok(out)
+}
+
+fn prune(root: &Path) -> anyhow::Result<()> {
+    for entry in list(root)?.into_iter().skip(KEEP) {
+        remove(&entry)?;
+    }
+    Ok(())
+}
+
+pub fn remove(entry: &TrashEntry) -> anyhow::Result<()> {
+    fs::remove_dir_all(&entry.dir)?;
+    Ok(())
+}
+
+// Puts the discarded content back and drops the entry. An existing path is only
+// overwritten when it is a tracked file back at HEAD, so later edits are never lost.
+pub fn restore(entry: &TrashEntry) -> anyhow::Result<()> {
+    let target = Path::new(&entry.meta.path);
+    match entry.meta.kind {
+        TrashKind::File | TrashKind::Dir => {
+            if fs::symlink_metadata(target).is_ok() && !can_overwrite(entry, target)? {
+                return Err(anyhow::anyhow!("{} exists and has changed since it was discarded", entry.display_path()));
+            }
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            copy_recursive(&entry.data_path(), target)?;
+        }
+        TrashKind::Patch => {
+            let patch = fs::read_to_string(entry.data_path())?;
+            git::apply_patch_at(Path::new(&entry.meta.repo), &patch, false, false)?;
+        }
+    }
+    remove(entry)
+}
+
+// A discarded tracked file is back at its HEAD content, which is safe to overwrite.
+fn can_overwrite(entry: &TrashEntry, target: &Path) -> anyhow::Result<bool> {
+    if entry.meta.kind == TrashKind::Dir || !target.is_file() {
+        return Ok(false);
+    }
+    let out = std::process::Command::new("git")
+        .arg("-C")
+        .arg(&entry.meta.repo)
+        .args(["status", "--porcelain", "--"])
+        .arg(target)
+        .output()?;
+    Ok(out.status.success() && out.stdout.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::system::system;
+    use crate::ui::common::TestEnv;
+
+    #[test]
+    fn test_trash_save_list_restore() {
+        let env = TestEnv::setup("test_trash_save_restore");
+        let trash = env.root.join("trash");
+        let repo = env.root.join("repo");
+        fs::create_dir_all(repo.join("dir")).unwrap();
+        fs::write(repo.join("new.txt"), "keep me\n").unwrap();
+        fs::write(repo.join("dir/a.txt"), "a\n").unwrap();
+
+        let file = save_path(&trash, &repo, &repo.join("new.txt")).unwrap().unwrap();
+        let dir = save_path(&trash, &repo, &repo.join("dir")).unwrap().unwrap();
+        assert!(save_path(&trash, &repo, &repo.join("missing")).unwrap().is_none());
+        fs::remove_file(repo.join("new.txt")).unwrap();
+        fs::remove_dir_all(repo.join("dir")).unwrap();
+
+        let entries = list(&trash).unwrap();
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].meta.kind, TrashKind::Dir);
+        assert_eq!(file.display_path(), "new.txt");
+
+        restore(&entries[1]).unwrap();
+        restore(&dir).unwrap();
+        assert_eq!(fs::read_to_string(repo.join("new.txt")).unwrap(), "keep me\n");
+        assert_eq!(fs::read_to_string(repo.join("dir/a.txt")).unwrap(), "a\n");
+        assert!(list(&trash).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_trash_restore_patch() {
+        let env = TestEnv::repo("test_trash_patch");
+        fs::write(env.root.join("file1"), "a\nb\n").unwrap();
+        let _ = system("git add file1");
+        fs::write(env.root.join("file1"), "a\nb changed\n").unwrap();
+
+        let repo = git::repo_root().unwrap();
+        let patch = git::file_diff("file1", false).unwrap() + "\n";
+        let entry = save_patch(&env.root.join("trash"), &repo, &repo.join("file1"), &patch).unwrap();
+        git::apply_patch_at(&repo, &patch, false, true).unwrap();
+        assert_eq!(fs::read_to_string(env.root.join("file1")).unwrap(), "a\nb\n");
+
+        restore(&entry).unwrap();
+        assert_eq!(fs::read_to_string(env.root.join("file1")).unwrap(), "a\nb changed\n");
+    }
+}
@@ -0,0 +1,158 @@
+use std::path::{Path, PathBuf};
+
+use crate::git::{self, BlameLine};
+use crate::ui::highlight::next_generation;
+
+// One blamed version of the file; re-blaming at a parent pushes the previous one.
+#[derive(Default)]
+pub struct BlameView {
+    // None for the working tree.
+    pub rev: Option<String>,
+    // Relative to the repo root, as the file was named at `rev`.
+    pub path: String,
+    pub lines: Vec<BlameLine>,
+    pub selected_idx: usize,
+}
+
+#[derive(Default)]
+pub struct BlameCtrl {
+    pub repo_root: PathBuf,
+    pub view: BlameView,
+    pub stack: Vec<BlameView>,
+    // Tab-expanded text of `view.lines`, for the syntax highlighter.
+    pub texts: Vec<String>,
+    // Generation of `texts`, for the syntax cache.
+    pub texts_gen: u64,
+}
+
+impl BlameCtrl {
+    // `file` is absolute or relative to the current directory; `line` is 0-based.
+    pub fn new(file: &Path, line: usize) -> anyhow::Result<Self> {
+        let abs = std::fs::canonicalize(file)?;
+        let repo_root = git::repo_root()?;
+        let root = std::fs::canonicalize(&repo_root)?;
+        let path = abs
+            .strip_prefix(&root)
+            .map_err(|_| anyhow::anyhow!("{} is not in the repository", file.display()))?
+            .to_string_lossy()
+            .to_string();
+        let mut ctrl = Self {
+            repo_root,
+            view: BlameView {
+                rev: None,
+                path: String::new(),
+                lines: Vec::new(),
+                selected_idx: 0,
+            },
+            stack: Vec::new(),
+            texts: Vec::new(),
+            texts_gen: 0,
+        };
+        ctrl.view = ctrl.load(None, &path)?;
+        ctrl.view.selected_idx = line.min(ctrl.view.lines.len().saturating_sub(1));
+        Ok(ctrl)
+    }
+
+    fn load(&mut self, rev: Option<&str>, path: &str) -> anyhow::Result<BlameView> {
+        let lines = git::blame_at(&self.repo_root, rev, path)?;
+        self.texts = lines.iter().map(|l| l.text.replace('\t', "    ")).collect();
+        self.texts_gen = next_generation();
+        Ok(BlameView {
+            rev: rev.map(|r| r.to_string()),
+            path: path.to_string(),
+            lines,
+            selected_idx: 0,
+        })
+    }
+
+    pub fn focus_line(&self) -> Option<&BlameLine> {
+        self.view.lines.get(self.view.selected_idx)
+    }
+
+    pub fn select(&mut self, idx: usize) {
+        self.view.selected_idx = idx.min(self.view.lines.len().saturating_sub(1));
+    }
+
+    pub fn is_uncommitted(line: &BlameLine) -> bool {
+        line.hash.bytes().all(|b| b == b'0')
+    }
+
+    // Blames the focused line's commit's parent, keeping about the same spot in the file.
+    pub fn blame_parent(&mut self) -> anyhow::Result<()> {
+        let Some(line) = self.focus_line() else {
+            return Ok(());
+        };
+        if Self::is_uncommitted(line) {
+            return Err(anyhow::anyhow!("Line is not committed yet"));
+        }
+        let Some((parent, path)) = line.previous.clone() else {
+            return Err(anyhow::anyhow!("{} added this file", &line.hash[..7]));
+        };
+        let texts = std::mem::take(&mut self.texts);
+        let view = match self.load(Some(&parent), &path) {
+            Ok(view) => view,
+            Err(err) => {
+                self.texts = texts;
+                return Err(err);
+            }
+        };
+        let selected = self.view.selected_idx;
+        self.stack.push(std::mem::replace(&mut self.view, view));
+        self.select(selected);
+        Ok(())
+    }
+
+    // Back to the blame before the last `blame_parent`; false when there is none.
+    pub fn pop(&mut self) -> bool {
+        let Some(view) = self.stack.pop() else {
+            return false;
+        };
+        self.texts = view.lines.iter().map(|l| l.text.replace('\t', "    ")).collect();
+        self.texts_gen = next_generation();
+        self.view = view;
+        true
+    }
+
+    pub fn title(&self) -> String {
+        match &self.view.rev {
+            Some(rev) => format!("{} @ {}", self.view.path, &rev[..rev.len().min(7)]),
+            None => self.view.path.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::system::system;
+    use crate::ui::common::TestEnv;
+
+    #[test]
+    fn test_blame_ctrl_lines_and_parent() {
+        let env = TestEnv::repo("test_blame_ctrl");
+        std::fs::write(env.root.join("a.txt"), "one\ntwo\n").unwrap();
+        let _ = system("git add . && git commit -q -m first");
+        std::fs::write(env.root.join("a.txt"), "one\nTWO\n").unwrap();
+        let _ = system("git commit -q -am second");
+        std::fs::write(env.root.join("a.txt"), "one\nTWO\nthree\n").unwrap();
+
+        let mut ctrl = BlameCtrl::new(Path::new("a.txt"), 1).unwrap();
+        assert_eq!(ctrl.view.lines.len(), 3);
+        assert_eq!(ctrl.texts, ["one", "TWO", "three"]);
+        let focus = ctrl.focus_line().unwrap();
+        assert_eq!((focus.summary.as_str(), focus.author.as_str(), focus.line_no), ("second", "tester", 2));
+        assert_eq!(ctrl.view.lines[0].summary, "first");
+        assert!(BlameCtrl::is_uncommitted(&ctrl.view.lines[2]));
+
+        ctrl.blame_parent().unwrap();
+        assert!(ctrl.title().starts_with("a.txt @ "));
+        assert_eq!(ctrl.texts, ["one", "two"]);
+        assert_eq!(ctrl.focus_line().unwrap().summary, "first");
+        assert!(ctrl.blame_parent().is_err());
+
+        assert!(ctrl.pop());
+        assert_eq!(ctrl.view.selected_idx, 1);
+        assert_eq!(ctrl.title(), "a.txt");
+        assert!(!ctrl.pop());
+    }
+}
@@ -0,0 +1,202 @@
+use std::path::Path;
+
+use crossterm::event::{KeyCode, KeyEvent, MouseEvent, MouseEventKind};
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span, Text};
+use ratatui::widgets::{Block, Paragraph};
+
+use crate::app::AppContext;
+use crate::system::app_log;
+use crate::ui::blame_ctrl::BlameCtrl;
+use crate::ui::common::{Action, Screen, mouse_pos};
+use crate::ui::highlight::SyntaxCache;
+use crate::util::{format_relative, now_secs};
+
+// Where `q` goes back to; Find is rebuilt from its file list.
+#[derive(Default)]
+pub enum BlameOrigin {
+    #[default]
+    Main,
+    GitStage,
+    Find(Vec<String>, usize),
+}
+
+// Newer lines stand out, old ones fade.
+fn age_color(age: i64) -> Color {
+    match age {
+        a if a < 86_400 => Color::LightGreen,
+        a if a < 7 * 86_400 => Color::Green,
+        a if a < 30 * 86_400 => Color::Cyan,
+        a if a < 365 * 86_400 => Color::Blue,
+        _ => Color::DarkGray,
+    }
+}
+
+#[derive(Default)]
+pub struct BlameState {
+    pub ctrl: BlameCtrl,
+    pub origin: BlameOrigin,
+    pub scroll: usize,
+    pub area: Option<Rect>,
+    pub syntax: SyntaxCache,
+}
+
+impl BlameState {
+    pub fn new(file: &Path, line: usize, origin: BlameOrigin) -> anyhow::Result<Self> {
+        Ok(Self {
+            ctrl: BlameCtrl::new(file, line)?,
+            origin,
+            scroll: 0,
+            area: None,
+            syntax: SyntaxCache::new(),
+        })
+    }
+
+    pub fn render(&mut self, f: &mut ratatui::Frame) {
+        let layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(1), Constraint::Length(1), Constraint::Min(1)])
+            .split(f.size());
+
+        let title = Paragraph::new(Line::from(Span::styled(
+            " >> Blame (Enter: open commit, p: blame parent, P: back, q: close)",
+            Style::default().fg(Color::Green).add_modifier(Modifier::BOLD),
+        )));
+        f.render_widget(title, layout[0]);
+        let summary = match self.ctrl.focus_line() {
+            Some(line) if !BlameCtrl::is_uncommitted(line) => format!(" {}  {} {}", self.ctrl.title(), &line.hash[..7], line.summary),
+            _ => format!(" {}", self.ctrl.title()),
+        };
+        f.render_widget(Paragraph::new(summary).style(Style::default().bg(Color::DarkGray).fg(Color::White)), layout[1]);
+
+        // Keep the focused line in view.
+        let height = layout[2].height as usize;
+        let selected = self.ctrl.view.selected_idx;
+        if selected < self.scroll {
+            self.scroll = selected;
+        } else if height > 0 && selected >= self.scroll + height {
+            self.scroll = selected + 1 - height;
+        }
+        let visible = self.scroll..self.scroll + height;
+        let path = self.ctrl.view.path.clone();
+        let mut texts = self.syntax.file_lines(&path, self.ctrl.texts_gen, &self.ctrl.texts, visible.clone());
+        let now = now_secs();
+        let lines: Vec<Line> = visible
+            .filter_map(|i| {
+                let blame = self.ctrl.view.lines.get(i)?;
+                // Commit info only on the first line of a run from the same commit.
+                let first = i == 0 || self.ctrl.view.lines[i - 1].hash != blame.hash;
+                let info = if !first {
+                    format!("{:<7} {:<12} {:<14}", "", "", "")
+                } else if BlameCtrl::is_uncommitted(blame) {
+                    format!("{:<7} {:<12} {:<14}", "·······", "(uncommitted)", "")
+                } else {
+                    let author: String = blame.author.chars().take(12).collect();
+                    format!("{} {:<12} {:<14}", &blame.hash[..7], author, format_relative(blame.time, now))
+                };
+                let mut style = Style::default().fg(age_color(now - blame.time));
+                if BlameCtrl::is_uncommitted(blame) {
+                    style = Style::default().fg(Color::Yellow);
+                }
+                let mut spans = vec![
+                    Span::styled(info, style),
+                    Span::styled(format!(" {:>5} ", blame.line_no), Style::default().fg(Color::DarkGray)),
+                ];
+                spans.extend(std::mem::take(&mut texts[i]).spans);
+                let mut line = Line::from(spans);
+                if i == selected {
+                    line = line.patch_style(Style::default().add_modifier(Modifier::REVERSED));
+                }
+                Some(line)
+            })
+            .collect();
+        f.render_widget(Paragraph::new(Text::from(lines)).block(Block::default()), layout[2]);
+        self.area = Some(layout[2]);
+    }
+
+    fn back(&self, ctx: &mut AppContext) -> anyhow::Result<Action> {
+        Ok(match &self.origin {
+            BlameOrigin::Main => Action::Switch(Screen::Main(Box::new(crate::ui::main_ui::MainState::new(ctx)?))),
+            BlameOrigin::GitStage => match crate::ui::git_stage_ui::GitStageState::new(ctx) {
+                Ok(state) => Action::Switch(Screen::GitStage(Box::new(state))),
+                Err(_) => Action::Switch(Screen::Main(Box::new(crate::ui::main_ui::MainState::new(ctx)?))),
+            },
+            BlameOrigin::Find(files, selected) => {
+                let state = crate::ui::find_ui::FindState::with_files(files.clone(), *selected);
+                Action::Switch(Screen::Find(Box::new(state)))
+            }
+        })
+    }
+
+    pub fn on_key(&mut self, ctx: &mut AppContext, key: KeyEvent) -> anyhow::Result<Action> {
+        let selected = self.ctrl.view.selected_idx;
+        let page = self.area.map(|a| a.height as usize).unwrap_or(10).max(1);
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return self.back(ctx),
+            KeyCode::Char('j') | KeyCode::Down => self.ctrl.select(selected + 1),
+            KeyCode::Char('k') | KeyCode::Up => self.ctrl.select(selected.saturating_sub(1)),
+            KeyCode::PageDown => self.ctrl.select(selected + page),
+            KeyCode::PageUp => self.ctrl.select(selected.saturating_sub(page)),
+            KeyCode::Char('g') => self.ctrl.select(0),
+            KeyCode::Char('G') => self.ctrl.select(usize::MAX),
+            KeyCode::Char('p') => {
+                if let Err(err) = self.ctrl.blame_parent() {
+                    return Ok(Action::Toast(err.to_string()));
+                }
+            }
+            KeyCode::Char('P') | KeyCode::Backspace if !self.ctrl.pop() => {
+                return Ok(Action::Toast("No earlier blame".to_string()));
+            }
+            KeyCode::Enter => {
+                let Some(line) = self.ctrl.focus_line() else {
+                    return Ok(Action::None);
+                };
+                if BlameCtrl::is_uncommitted(line) {
+                    return Ok(Action::Toast("Line is not committed yet".to_string()));
+                }
+                let mut state = match crate::ui::git_history_ui::GitHistoryState::at_rev(ctx, &line.hash) {
+                    Ok(state) => state,
+                    Err(err) => {
+                        app_log(&format!("Blame history error: {}", err));
+                        return Ok(Action::Toast(format!("Cannot open history: {}", err)));
+                    }
+                };
+                // This screen is being left, so it moves into the history to come back to.
+                state.origin = crate::ui::git_history_ui::HistoryOrigin::Blame(Box::new(std::mem::take(self)));
+                return Ok(Action::Switch(Screen::GitHistory(Box::new(state))));
+            }
+            _ => {}
+        }
+        Ok(Action::None)
+    }
+
+    pub fn on_mouse(&mut self, _ctx: &mut AppContext, me: MouseEvent) -> anyhow::Result<Action> {
+        let Some(area) = self.area else {
+            return Ok(Action::None);
+        };
+        if !area.contains(mouse_pos(&me)) {
+            return Ok(Action::None);
+        }
+        let selected = self.ctrl.view.selected_idx;
+        match me.kind {
+            MouseEventKind::Down(_) => self.ctrl.select(self.scroll + (me.row - area.y) as usize),
+            MouseEventKind::ScrollDown => self.ctrl.select(selected + 3),
+            MouseEventKind::ScrollUp => self.ctrl.select(selected.saturating_sub(3)),
+            _ => {}
+        }
+        Ok(Action::None)
+    }
+}
+
+impl crate::ui::common::ScreenState for BlameState {
+    fn render(&mut self, f: &mut ratatui::Frame) {
+        self.render(f);
+    }
+    fn on_key(&mut self, ctx: &mut AppContext, key: KeyEvent) -> anyhow::Result<Action> {
+        self.on_key(ctx, key)
+    }
+    fn on_mouse(&mut self, ctx: &mut AppContext, me: MouseEvent) -> anyhow::Result<Action> {
+        self.on_mouse(ctx, me)
+    }
+}
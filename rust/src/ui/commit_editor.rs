@@ -0,0 +1,427 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::style::{Color, Style};
+use ratatui::text::{Line, Span};
+use regex::Regex;
+use unicode_width::UnicodeWidthStr;
+
+use crate::config::CommitRules;
+
+pub const SUBJECT_LIMIT: usize = 50;
+pub const BODY_LIMIT: usize = 72;
+
+// Commit message being edited: line 0 is the subject, the rest is the body.
+// `col` counts chars, not bytes.
+pub struct CommitEditor {
+    pub lines: Vec<String>,
+    pub row: usize,
+    pub col: usize,
+}
+
+fn byte_pos(line: &str, col: usize) -> usize {
+    line.char_indices().nth(col).map(|(i, _)| i).unwrap_or(line.len())
+}
+
+fn char_len(line: &str) -> usize {
+    line.chars().count()
+}
+
+// Greedy word wrap; a word longer than `width` gets a row of its own.
+pub fn wrap_line(line: &str, width: usize) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut cur = String::new();
+    for (i, word) in line.split(' ').enumerate() {
+        if i == 0 {
+            cur.push_str(word);
+        } else if !cur.trim().is_empty() && char_len(&cur) + 1 + char_len(word) > width {
+            out.push(std::mem::take(&mut cur));
+            cur.push_str(word);
+        } else {
+            cur.push(' ');
+            cur.push_str(word);
+        }
+    }
+    out.push(cur);
+    out
+}
+
+// Text up to `limit` columns, then a ruler mark, or the overflow in red.
+pub fn ruler_line(text: &str, limit: usize) -> Line<'static> {
+    let chars: Vec<char> = text.chars().collect();
+    if chars.len() > limit {
+        let head: String = chars[..limit].iter().collect();
+        let tail: String = chars[limit..].iter().collect();
+        Line::from(vec![Span::raw(head), Span::styled(tail, Style::default().fg(Color::Red))])
+    } else {
+        let pad = " ".repeat(limit - chars.len());
+        Line::from(vec![
+            Span::raw(format!("{}{}", text, pad)),
+            Span::styled("│", Style::default().fg(Color::DarkGray)),
+        ])
+    }
+}
+
+// `requiredPattern` compiled once per rule set; None when unset or invalid.
+pub fn required_regex(rules: &CommitRules) -> Option<Regex> {
+    rules.required_pattern.as_deref().and_then(|p| Regex::new(p).ok())
+}
+
+// Rule violations of `message` when committed on `branch`; empty when it passes.
+// `required` is `required_regex(rules)`.
+pub fn lint_message(rules: &CommitRules, required: Option<&Regex>, message: &str, branch: &str) -> Vec<String> {
+    let mut out = Vec::new();
+    let subject = message.lines().next().unwrap_or("");
+    if let Some(max) = rules.max_subject_length {
+        let len = char_len(subject);
+        if len > max {
+            out.push(format!("subject is {} chars (max {})", len, max));
+        }
+    }
+    if let Some(pattern) = &rules.required_pattern {
+        match required {
+            Some(re) if !re.is_match(message) => out.push(format!("message must match /{}/", pattern)),
+            None => out.push(format!("invalid requiredPattern /{}/", pattern)),
+            _ => {}
+        }
+    }
+    let protected = match &rules.protected_branches {
+        Some(branches) if !branches.is_empty() => branches.iter().any(|b| b == branch),
+        _ => true,
+    };
+    if protected {
+        let words: Vec<String> = message
+            .split(|c: char| !c.is_alphanumeric() && c != '_')
+            .map(|w| w.to_lowercase())
+            .collect();
+        for forbidden in rules.forbidden_words.iter().flatten() {
+            if words.contains(&forbidden.to_lowercase()) {
+                out.push(format!("'{}' is not allowed on {}", forbidden, branch));
+            }
+        }
+    }
+    out
+}
+
+impl Default for CommitEditor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CommitEditor {
+    pub fn new() -> Self {
+        Self {
+            lines: vec![String::new()],
+            row: 0,
+            col: 0,
+        }
+    }
+
+    // Loads an existing message; the blank line after the subject is implied.
+    pub fn from_message(message: &str) -> Self {
+        let mut lines: Vec<String> = message.trim_end().lines().map(|l| l.to_string()).collect();
+        if lines.is_empty() {
+            lines.push(String::new());
+        }
+        if lines.len() > 1 && lines[1].trim().is_empty() {
+            lines.remove(1);
+        }
+        let col = char_len(&lines[0]);
+        Self { lines, row: 0, col }
+    }
+
+    pub fn subject(&self) -> &str {
+        &self.lines[0]
+    }
+
+    pub fn set_subject(&mut self, subject: &str) {
+        self.lines[0] = subject.to_string();
+        if self.row == 0 {
+            self.col = char_len(subject);
+        }
+    }
+
+    // Puts `prefix` in front of the subject, replacing any of the `known` presets already there.
+    pub fn set_prefix(&mut self, prefix: &str, known: &[String]) {
+        let subject = self.lines[0].trim_start();
+        let rest = known
+            .iter()
+            .find_map(|p| subject.strip_prefix(p.as_str()))
+            .unwrap_or(subject)
+            .trim_start();
+        let subject = format!("{} {}", prefix, rest);
+        self.row = 0;
+        self.set_subject(&subject);
+    }
+
+    pub fn body(&self) -> &[String] {
+        &self.lines[1..]
+    }
+
+    pub fn in_subject(&self) -> bool {
+        self.row == 0
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines[0].trim().is_empty()
+    }
+
+    // Subject and body joined by a blank line, surrounding blank body lines dropped.
+    pub fn message(&self) -> String {
+        let subject = self.lines[0].trim();
+        let body: Vec<&str> = self.lines[1..].iter().map(|l| l.trim_end()).collect();
+        let Some(start) = body.iter().position(|l| !l.is_empty()) else {
+            return subject.to_string();
+        };
+        let end = body.iter().rposition(|l| !l.is_empty()).unwrap_or(start);
+        format!("{}\n\n{}", subject, body[start..=end].join("\n"))
+    }
+
+    // The message with body lines wrapped at `BODY_LIMIT`, as a preview of a reflow.
+    pub fn wrapped_preview(&self) -> Vec<String> {
+        let message = self.message();
+        let mut out = Vec::new();
+        for (i, line) in message.lines().enumerate() {
+            if i == 0 || line.is_empty() {
+                out.push(line.to_string());
+            } else {
+                out.extend(wrap_line(line, BODY_LIMIT));
+            }
+        }
+        out
+    }
+
+    // Terminal columns taken by the text before the cursor, for placing it on screen.
+    pub fn cursor_width(&self) -> usize {
+        let line = &self.lines[self.row];
+        line[..byte_pos(line, self.col)].width()
+    }
+
+    fn line_len(&self) -> usize {
+        char_len(&self.lines[self.row])
+    }
+
+    pub fn insert_char(&mut self, c: char) {
+        let line = &mut self.lines[self.row];
+        let pos = byte_pos(line, self.col);
+        line.insert(pos, c);
+        self.col += 1;
+    }
+
+    // Enter on the subject jumps to the body; in the body it splits the line.
+    pub fn newline(&mut self) {
+        if self.row == 0 {
+            if self.lines.len() == 1 {
+                self.lines.push(String::new());
+            }
+            self.row = 1;
+            self.col = 0;
+            return;
+        }
+        let line = &mut self.lines[self.row];
+        let rest = line.split_off(byte_pos(line, self.col));
+        self.lines.insert(self.row + 1, rest);
+        self.row += 1;
+        self.col = 0;
+    }
+
+    pub fn backspace(&mut self) {
+        if self.col > 0 {
+            let line = &mut self.lines[self.row];
+            let pos = byte_pos(line, self.col - 1);
+            line.remove(pos);
+            self.col -= 1;
+        } else if self.row > 1 {
+            let line = self.lines.remove(self.row);
+            self.row -= 1;
+            self.col = self.line_len();
+            self.lines[self.row].push_str(&line);
+        } else if self.row == 1 {
+            // The subject never absorbs body text; just step back into it.
+            if self.lines[1].is_empty() && self.lines.len() == 2 {
+                self.lines.pop();
+            }
+            self.row = 0;
+            self.col = self.line_len();
+        }
+    }
+
+    pub fn delete(&mut self) {
+        if self.col < self.line_len() {
+            let line = &mut self.lines[self.row];
+            let pos = byte_pos(line, self.col);
+            line.remove(pos);
+        } else if self.row > 0 && self.row + 1 < self.lines.len() {
+            let next = self.lines.remove(self.row + 1);
+            self.lines[self.row].push_str(&next);
+        }
+    }
+
+    pub fn left(&mut self) {
+        if self.col > 0 {
+            self.col -= 1;
+        } else if self.row > 0 {
+            self.row -= 1;
+            self.col = self.line_len();
+        }
+    }
+
+    pub fn right(&mut self) {
+        if self.col < self.line_len() {
+            self.col += 1;
+        } else if self.row + 1 < self.lines.len() {
+            self.row += 1;
+            self.col = 0;
+        }
+    }
+
+    pub fn up(&mut self) {
+        if self.row > 0 {
+            self.row -= 1;
+            self.col = self.col.min(self.line_len());
+        }
+    }
+
+    pub fn down(&mut self) {
+        if self.row + 1 < self.lines.len() {
+            self.row += 1;
+            self.col = self.col.min(self.line_len());
+        }
+    }
+
+    pub fn home(&mut self) {
+        self.col = 0;
+    }
+
+    pub fn end(&mut self) {
+        self.col = self.line_len();
+    }
+
+    // Plain editing keys; false when `key` is not one of them.
+    pub fn edit_key(&mut self, key: KeyEvent) -> bool {
+        match key.code {
+            KeyCode::Enter => self.newline(),
+            KeyCode::Tab => self.toggle_section(),
+            KeyCode::Backspace => self.backspace(),
+            KeyCode::Delete => self.delete(),
+            KeyCode::Left => self.left(),
+            KeyCode::Right => self.right(),
+            KeyCode::Up => self.up(),
+            KeyCode::Down => self.down(),
+            KeyCode::Home => self.home(),
+            KeyCode::End => self.end(),
+            KeyCode::Char(c) if !c.is_control() && !key.modifiers.contains(KeyModifiers::CONTROL) => self.insert_char(c),
+            _ => return false,
+        }
+        true
+    }
+
+    // Tab switches between the subject and the body.
+    pub fn toggle_section(&mut self) {
+        if self.row == 0 {
+            if self.lines.len() == 1 {
+                self.lines.push(String::new());
+            }
+            self.row = 1;
+        } else {
+            self.row = 0;
+        }
+        self.col = self.line_len();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn type_str(editor: &mut CommitEditor, text: &str) {
+        for c in text.chars() {
+            if c == '\n' {
+                editor.newline();
+            } else {
+                editor.insert_char(c);
+            }
+        }
+    }
+
+    #[test]
+    fn test_commit_editor_subject_and_body() {
+        let mut editor = CommitEditor::new();
+        type_str(&mut editor, "Fix parser\nfirst line\nsecond line\n\n");
+        assert_eq!(editor.subject(), "Fix parser");
+        assert_eq!(editor.body().len(), 4);
+        assert_eq!(editor.message(), "Fix parser\n\nfirst line\nsecond line");
+
+        // Backspace at the start of the body returns to the subject without joining.
+        editor.row = 1;
+        editor.col = 0;
+        editor.backspace();
+        assert_eq!((editor.row, editor.col), (0, 10));
+        assert_eq!(editor.subject(), "Fix parser");
+
+        editor.down();
+        editor.end();
+        editor.delete();
+        assert_eq!(editor.body()[0], "first linesecond line");
+    }
+
+    #[test]
+    fn test_commit_editor_from_message_round_trip() {
+        let editor = CommitEditor::from_message("Subject\n\nbody 1\n\nbody 2\n");
+        assert_eq!(editor.body(), &["body 1".to_string(), "".to_string(), "body 2".to_string()]);
+        assert_eq!(editor.message(), "Subject\n\nbody 1\n\nbody 2");
+        assert_eq!(CommitEditor::from_message("").message(), "");
+    }
+
+    #[test]
+    fn test_commit_editor_set_prefix_replaces_preset() {
+        let known = vec!["feat:".to_string(), "fix:".to_string()];
+        let mut editor = CommitEditor::from_message("feat: add parser");
+        editor.set_prefix("fix:", &known);
+        assert_eq!(editor.subject(), "fix: add parser");
+        let mut editor = CommitEditor::from_message("plain subject");
+        editor.set_prefix("feat:", &known);
+        assert_eq!(editor.subject(), "feat: plain subject");
+        assert_eq!(editor.col, editor.subject().chars().count());
+    }
+
+    #[test]
+    fn test_lint_message_rules() {
+        let rules = CommitRules {
+            max_subject_length: Some(10),
+            required_pattern: Some(r"[A-Z]+-\d+".to_string()),
+            forbidden_words: Some(vec!["WIP".to_string()]),
+            protected_branches: Some(vec!["main".to_string()]),
+            block: None,
+        };
+        let re = required_regex(&rules);
+        let found = lint_message(&rules, re.as_ref(), "wip: long subject here\nbody", "main");
+        assert_eq!(found.len(), 3);
+        assert_eq!(lint_message(&rules, re.as_ref(), "wip: long subject here\nbody", "topic").len(), 2);
+        assert!(lint_message(&rules, re.as_ref(), "ABC-12 ok\n\nbody", "main").is_empty());
+        let bad = CommitRules { required_pattern: Some("(".to_string()), ..Default::default() };
+        assert_eq!(lint_message(&bad, required_regex(&bad).as_ref(), "x", "main"), ["invalid requiredPattern /(/"]);
+    }
+
+    #[test]
+    fn test_commit_editor_multibyte_cursor() {
+        let mut editor = CommitEditor::new();
+        type_str(&mut editor, "한글 제목");
+        editor.left();
+        editor.left();
+        assert_eq!(editor.cursor_width(), 5);
+        editor.backspace();
+        assert_eq!(editor.subject(), "한글제목");
+    }
+
+    #[test]
+    fn test_wrapped_preview_wraps_body_at_limit() {
+        let mut editor = CommitEditor::new();
+        let long = "word ".repeat(20);
+        type_str(&mut editor, &format!("Subject\n{}", long.trim_end()));
+        let preview = editor.wrapped_preview();
+        assert_eq!(preview[0], "Subject");
+        assert_eq!(preview[1], "");
+        assert!(preview.len() > 3);
+        assert!(preview[2..].iter().all(|l| l.chars().count() <= BODY_LIMIT));
+    }
+}
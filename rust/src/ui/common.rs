@@ -8,6 +8,7 @@ use crossterm::event::{DisableMouseCapture, EnableMouseCapture};
 use ratatui::layout::{Constraint, Direction, Layout, Position, Rect};
 use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::{Line, Span};
+use unicode_width::UnicodeWidthChar;
 
 use crate::app::AppContext;
 
@@ -30,6 +31,11 @@ pub enum Screen {
     GitCommit(Box<dyn ScreenState>),
     RegList(Box<dyn ScreenState>),
     Goto(Box<dyn ScreenState>),
+    Trash(Box<dyn ScreenState>),
+    Stash(Box<dyn ScreenState>),
+    Conflict(Box<dyn ScreenState>),
+    Rebase(Box<dyn ScreenState>),
+    Blame(Box<dyn ScreenState>),
 }
 
 pub trait ScreenState {
@@ -38,73 +44,188 @@ pub trait ScreenState {
     fn on_mouse(&mut self, ctx: &mut AppContext, me: MouseEvent) -> anyhow::Result<Action>;
 }
 
+fn diff_line_style(line: &str) -> Style {
+    if line.starts_with("diff --git") {
+        Style::default().add_modifier(Modifier::BOLD)
+    } else if line.starts_with("index ") {
+        Style::default().fg(Color::DarkGray)
+    } else if line.starts_with("--- ") {
+        Style::default().fg(Color::Red)
+    } else if line.starts_with("+++ ") {
+        Style::default().fg(Color::Green)
+    } else if line.starts_with("@@") {
+        Style::default().fg(Color::Cyan)
+    } else if line.starts_with('+') {
+        Style::default().fg(Color::Green)
+    } else if line.starts_with('-') {
+        Style::default().fg(Color::Red)
+    } else {
+        Style::default()
+    }
+}
+
+fn hunk_rule(width: u16) -> Line<'static> {
+    Line::from(Span::styled(
+        "-".repeat(width.max(1) as usize),
+        Style::default().fg(Color::DarkGray),
+    ))
+}
+
 pub fn format_diff_lines(lines: &[String], width: u16) -> Vec<Line<'static>> {
     let mut out = Vec::new();
     let mut first_hunk = true;
-    let rule_len = width.max(1) as usize;
     for line in lines {
-        if line.starts_with("diff --git") {
-            out.push(Line::from(Span::styled(
-                line.clone(),
-                Style::default().add_modifier(Modifier::BOLD),
-            )));
-            continue;
-        }
-        if line.starts_with("index ") {
-            out.push(Line::from(Span::styled(
-                line.clone(),
-                Style::default().fg(Color::DarkGray),
-            )));
-            continue;
-        }
-        if line.starts_with("--- ") {
-            out.push(Line::from(Span::styled(
-                line.clone(),
-                Style::default().fg(Color::Red),
-            )));
-            continue;
+        if line.starts_with("@@") {
+            if !first_hunk {
+                out.push(hunk_rule(width));
+            }
+            first_hunk = false;
         }
-        if line.starts_with("+++ ") {
-            out.push(Line::from(Span::styled(
-                line.clone(),
-                Style::default().fg(Color::Green),
-            )));
-            continue;
+        out.push(Line::from(Span::styled(line.clone(), diff_line_style(line))));
+    }
+    out
+}
+
+enum SideRow {
+    Full(usize),
+    Rule,
+    // (line index, line number) for the old and new column.
+    Pair(Option<(usize, usize)>, Option<(usize, usize)>),
+}
+
+fn side_by_side_layout(lines: &[String]) -> Vec<SideRow> {
+    fn flush(rows: &mut Vec<SideRow>, dels: &mut Vec<(usize, usize)>, adds: &mut Vec<(usize, usize)>) {
+        for i in 0..dels.len().max(adds.len()) {
+            rows.push(SideRow::Pair(dels.get(i).copied(), adds.get(i).copied()));
         }
+        dels.clear();
+        adds.clear();
+    }
+
+    let mut rows = Vec::new();
+    let mut dels = Vec::new();
+    let mut adds = Vec::new();
+    let mut in_hunk = false;
+    let mut first_hunk = true;
+    let (mut old_no, mut new_no) = (0, 0);
+    for (i, line) in lines.iter().enumerate() {
         if line.starts_with("@@") {
+            flush(&mut rows, &mut dels, &mut adds);
             if !first_hunk {
-                out.push(Line::from(Span::styled(
-                    "-".repeat(rule_len),
-                    Style::default().fg(Color::DarkGray),
-                )));
+                rows.push(SideRow::Rule);
             }
             first_hunk = false;
-            out.push(Line::from(Span::styled(
-                line.clone(),
-                Style::default().fg(Color::Cyan),
-            )));
+            rows.push(SideRow::Full(i));
+            if let Some((old_start, _, new_start, _)) = crate::git::parse_hunk_header(line) {
+                old_no = old_start;
+                new_no = new_start;
+                in_hunk = true;
+            }
+            continue;
+        }
+        if in_hunk && line.starts_with('-') {
+            dels.push((i, old_no));
+            old_no += 1;
             continue;
         }
-        if line.starts_with('+') {
-            out.push(Line::from(Span::styled(
-                line.clone(),
-                Style::default().fg(Color::Green),
-            )));
+        if in_hunk && line.starts_with('+') {
+            adds.push((i, new_no));
+            new_no += 1;
             continue;
         }
-        if line.starts_with('-') {
-            out.push(Line::from(Span::styled(
-                line.clone(),
-                Style::default().fg(Color::Red),
-            )));
+        flush(&mut rows, &mut dels, &mut adds);
+        if in_hunk && line.starts_with(' ') {
+            rows.push(SideRow::Pair(Some((i, old_no)), Some((i, new_no))));
+            old_no += 1;
+            new_no += 1;
             continue;
         }
+        if !line.starts_with('\\') {
+            in_hunk = false;
+        }
+        rows.push(SideRow::Full(i));
+    }
+    flush(&mut rows, &mut dels, &mut adds);
+    rows
+}
 
-        out.push(Line::from(Span::styled(line.clone(), Style::default())));
+// Cut and padded to `width` terminal columns, so wide characters keep the columns aligned.
+fn side_cell(lines: &[String], cell: Option<(usize, usize)>, width: usize) -> Span<'static> {
+    let Some((idx, no)) = cell else {
+        return Span::raw(" ".repeat(width));
+    };
+    let line = &lines[idx];
+    let mut text = String::new();
+    let mut used = 0;
+    for c in format!("{:>4} {}", no, line.get(1..).unwrap_or("")).chars() {
+        let w = c.width().unwrap_or(0);
+        if used + w > width {
+            break;
+        }
+        text.push(c);
+        used += w;
     }
-    out
+    let pad = width - used;
+    let style = if line.starts_with(' ') {
+        Style::default()
+    } else {
+        diff_line_style(line)
+    };
+    Span::styled(format!("{}{}", text, " ".repeat(pad)), style)
+}
+
+// Renders the hunks of a unified diff as two aligned columns (old | new).
+pub fn format_diff_side_by_side(lines: &[String], width: u16) -> Vec<Line<'static>> {
+    let col = (width as usize).saturating_sub(3) / 2;
+    side_by_side_layout(lines)
+        .into_iter()
+        .map(|row| match row {
+            SideRow::Full(i) => Line::from(Span::styled(lines[i].clone(), diff_line_style(&lines[i]))),
+            SideRow::Rule => hunk_rule(width),
+            SideRow::Pair(old, new) => Line::from(vec![
+                side_cell(lines, old, col),
+                Span::styled(" | ", Style::default().fg(Color::DarkGray)),
+                side_cell(lines, new, col),
+            ]),
+        })
+        .collect()
 }
 
+// Row of `lines[idx]` once rendered by `format_diff_side_by_side`.
+pub fn side_by_side_row(lines: &[String], idx: usize) -> usize {
+    side_by_side_layout(lines)
+        .iter()
+        .position(|row| match row {
+            SideRow::Full(i) => *i == idx,
+            SideRow::Rule => false,
+            SideRow::Pair(old, new) => {
+                old.is_some_and(|(i, _)| i == idx) || new.is_some_and(|(i, _)| i == idx)
+            }
+        })
+        .unwrap_or(idx)
+}
+
+// Row of `lines[idx]` once rendered by `format_diff_lines`, which adds a rule before each later hunk.
+pub fn diff_display_row(lines: &[String], idx: usize) -> usize {
+    let hunks_before = lines
+        .iter()
+        .take(idx)
+        .filter(|l| l.starts_with("@@"))
+        .count();
+    let rules = if lines.get(idx).is_some_and(|l| l.starts_with("@@")) {
+        hunks_before
+    } else {
+        hunks_before.saturating_sub(1)
+    };
+    idx + rules
+}
+
+// Flips the side-by-side diff preference shared by the diff screens and saves it.
+pub fn toggle_side_by_side(ctx: &mut AppContext) -> anyhow::Result<bool> {
+    ctx.config.diff_side_by_side = !ctx.config.diff_side_by_side;
+    ctx.save()?;
+    Ok(ctx.config.diff_side_by_side)
+}
 
 pub fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
     let popup_layout = Layout::default()
@@ -189,6 +310,14 @@ where
             
             Self { root, old_cwd }
         }
+
+        // `setup` plus an empty git repo on `main` with a committer identity.
+        pub fn repo(prefix: &str) -> Self {
+            let env = Self::setup(prefix);
+            let _ = crate::system::system("git init -q -b main");
+            let _ = crate::system::system("git config user.name tester && git config user.email tester@example.com");
+            env
+        }
     }
     
     #[cfg(test)]
@@ -230,4 +359,34 @@ where
         assert_eq!(out[2].spans[0].style.fg, Some(Color::Green));
         assert_eq!(out[3].spans[0].style.fg, None);
     }
+
+    #[test]
+    fn test_format_diff_side_by_side_pairs_changes() {
+        let lines = vec![
+            "@@ -10,3 +10,3 @@".to_string(),
+            " context".to_string(),
+            "-old line".to_string(),
+            "+new line".to_string(),
+            "+extra".to_string(),
+        ];
+        let out = format_diff_side_by_side(&lines, 43);
+        assert_eq!(out.len(), 4);
+        assert_eq!(out[0].spans[0].style.fg, Some(Color::Cyan));
+        assert_eq!(out[1].spans[0].content, "  10 context        ");
+        assert_eq!(out[1].spans[2].content, "  10 context        ");
+        assert_eq!(out[2].spans[0].content, "  11 old line       ");
+        assert_eq!(out[2].spans[0].style.fg, Some(Color::Red));
+        assert_eq!(out[2].spans[2].content, "  11 new line       ");
+        assert_eq!(out[2].spans[2].style.fg, Some(Color::Green));
+        assert_eq!(out[3].spans[0].content.trim(), "");
+        assert_eq!(out[3].spans[2].content, "  12 extra          ");
+
+        assert_eq!(side_by_side_row(&lines, 3), 2);
+        assert_eq!(side_by_side_row(&lines, 4), 3);
+
+        // Each Hangul syllable takes two columns; the odd one out becomes padding.
+        let wide = vec!["@@ -1 +1 @@".to_string(), "-한글한글한글한글한글".to_string(), "+x".to_string()];
+        let out = format_diff_side_by_side(&wide, 43);
+        assert_eq!(out[1].spans[0].content, "   1 한글한글한글한 ");
+    }
     
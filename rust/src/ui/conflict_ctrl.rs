@@ -0,0 +1,371 @@
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::git;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Pick {
+    Ours,
+    Theirs,
+    // Ours followed by theirs.
+    Both,
+}
+
+// One `<<<<<<< ... >>>>>>>` region; `start`/`end` are the marker line indices.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConflictBlock {
+    pub start: usize,
+    pub end: usize,
+    pub ours_label: String,
+    pub theirs_label: String,
+    pub ours: Vec<String>,
+    // Only present with `merge.conflictStyle=diff3`/`zdiff3` markers or when recovered from the index.
+    pub base: Option<Vec<String>>,
+    pub theirs: Vec<String>,
+}
+
+fn marker<'a>(line: &'a str, mark: &str) -> Option<&'a str> {
+    let rest = line.trim_end_matches('\r').strip_prefix(mark)?;
+    if rest.is_empty() {
+        Some("")
+    } else {
+        rest.strip_prefix(' ')
+    }
+}
+
+pub fn parse_conflicts(lines: &[String]) -> Vec<ConflictBlock> {
+    enum Part {
+        Ours,
+        Base,
+        Theirs,
+    }
+    let mut out = Vec::new();
+    let mut cur: Option<(ConflictBlock, Part)> = None;
+    for (i, line) in lines.iter().enumerate() {
+        if let Some(label) = marker(line, "<<<<<<<") {
+            let block = ConflictBlock {
+                start: i,
+                end: i,
+                ours_label: label.to_string(),
+                theirs_label: String::new(),
+                ours: Vec::new(),
+                base: None,
+                theirs: Vec::new(),
+            };
+            cur = Some((block, Part::Ours));
+            continue;
+        }
+        let Some((block, part)) = cur.as_mut() else {
+            continue;
+        };
+        if marker(line, "|||||||").is_some() && matches!(part, Part::Ours) {
+            block.base = Some(Vec::new());
+            *part = Part::Base;
+        } else if line.trim_end_matches('\r') == "=======" && !matches!(part, Part::Theirs) {
+            *part = Part::Theirs;
+        } else if let (Some(label), Part::Theirs) = (marker(line, ">>>>>>>"), &part) {
+            block.end = i;
+            block.theirs_label = label.to_string();
+            if let Some((block, _)) = cur.take() {
+                out.push(block);
+            }
+        } else {
+            match part {
+                Part::Ours => block.ours.push(line.clone()),
+                Part::Base => block.base.get_or_insert_with(Vec::new).push(line.clone()),
+                Part::Theirs => block.theirs.push(line.clone()),
+            }
+        }
+    }
+    out
+}
+
+pub fn resolve_block(lines: &[String], block: &ConflictBlock, pick: Pick) -> Vec<String> {
+    let mut out = lines[..block.start].to_vec();
+    match pick {
+        Pick::Ours => out.extend(block.ours.iter().cloned()),
+        Pick::Theirs => out.extend(block.theirs.iter().cloned()),
+        Pick::Both => {
+            out.extend(block.ours.iter().cloned());
+            out.extend(block.theirs.iter().cloned());
+        }
+    }
+    out.extend(lines[block.end + 1..].iter().cloned());
+    out
+}
+
+fn split_lines(text: &str) -> Vec<String> {
+    let body = text.strip_suffix('\n').unwrap_or(text);
+    if body.is_empty() {
+        return Vec::new();
+    }
+    body.split('\n').map(|l| l.to_string()).collect()
+}
+
+// Re-merges the three index stages with diff3 markers to recover the base of each block.
+fn diff3_blocks(root: &Path, path: &str) -> Vec<ConflictBlock> {
+    let (Some(ours), Some(theirs)) = (git::conflict_stage_at(root, 2, path), git::conflict_stage_at(root, 3, path)) else {
+        return Vec::new();
+    };
+    let base = git::conflict_stage_at(root, 1, path).unwrap_or_default();
+    let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_nanos()).unwrap_or(0);
+    let dir = std::env::temp_dir().join(format!("sc-merge-{}-{}", std::process::id(), nanos));
+    if std::fs::create_dir_all(&dir).is_err() {
+        return Vec::new();
+    }
+    let files = [("ours", ours), ("base", base), ("theirs", theirs)];
+    for (name, text) in &files {
+        let _ = std::fs::write(dir.join(name), text);
+    }
+    // Exit code is the number of conflicts, so stdout is used regardless of status.
+    let out = std::process::Command::new("git")
+        .args(["merge-file", "-p", "--diff3"])
+        .arg(dir.join("ours"))
+        .arg(dir.join("base"))
+        .arg(dir.join("theirs"))
+        .output();
+    let _ = std::fs::remove_dir_all(&dir);
+    match out {
+        Ok(out) => parse_conflicts(&split_lines(&String::from_utf8_lossy(&out.stdout))),
+        Err(_) => Vec::new(),
+    }
+}
+
+pub struct ConflictCtrl {
+    pub repo_root: PathBuf,
+    // Repo-relative paths that were unmerged when the screen opened.
+    pub files: Vec<String>,
+    pub resolved: BTreeSet<String>,
+    pub selected_idx: usize,
+    pub lines: Vec<String>,
+    pub trailing_newline: bool,
+    pub blocks: Vec<ConflictBlock>,
+    pub block_idx: usize,
+    pub content_scroll: u16,
+    // Shown instead of blocks, e.g. for a file deleted on one side.
+    pub note: Option<String>,
+}
+
+impl ConflictCtrl {
+    // `focus` is the file to select first.
+    pub fn new(focus: Option<&Path>) -> anyhow::Result<Self> {
+        let repo_root = git::repo_root()?;
+        let files = git::conflicted_files_at(&repo_root)?;
+        if files.is_empty() {
+            return Err(anyhow::anyhow!("No conflicted files"));
+        }
+        let focus = focus.and_then(|f| f.canonicalize().ok());
+        let selected_idx = focus
+            .and_then(|f| files.iter().position(|p| repo_root.join(p).canonicalize().ok().as_ref() == Some(&f)))
+            .unwrap_or(0);
+        let mut ctrl = Self {
+            repo_root,
+            files,
+            resolved: BTreeSet::new(),
+            selected_idx,
+            lines: Vec::new(),
+            trailing_newline: true,
+            blocks: Vec::new(),
+            block_idx: 0,
+            content_scroll: 0,
+            note: None,
+        };
+        ctrl.load_file();
+        Ok(ctrl)
+    }
+
+    pub fn focus_file(&self) -> Option<&String> {
+        self.files.get(self.selected_idx)
+    }
+
+    pub fn focus_path(&self) -> Option<PathBuf> {
+        self.focus_file().map(|f| self.repo_root.join(f))
+    }
+
+    pub fn is_resolved(&self, file: &str) -> bool {
+        self.resolved.contains(file)
+    }
+
+    pub fn focus_block(&self) -> Option<&ConflictBlock> {
+        self.blocks.get(self.block_idx)
+    }
+
+    pub fn load_file(&mut self) {
+        self.lines.clear();
+        self.blocks.clear();
+        self.note = None;
+        let (Some(file), Some(path)) = (self.focus_file().cloned(), self.focus_path()) else {
+            return;
+        };
+        match std::fs::read(&path) {
+            Ok(bytes) => {
+                let text = String::from_utf8_lossy(&bytes);
+                self.trailing_newline = text.ends_with('\n');
+                self.lines = split_lines(&text);
+            }
+            Err(_) if self.is_resolved(&file) => return,
+            Err(_) => {
+                self.note = Some(format!("{} was deleted on one side (o: keep ours, t: keep theirs)", file));
+                return;
+            }
+        }
+        self.blocks = parse_conflicts(&self.lines);
+        if self.blocks.iter().any(|b| b.base.is_none()) {
+            let merged = diff3_blocks(&self.repo_root, &file);
+            for block in self.blocks.iter_mut().filter(|b| b.base.is_none()) {
+                block.base = merged
+                    .iter()
+                    .find(|m| m.ours == block.ours && m.theirs == block.theirs)
+                    .and_then(|m| m.base.clone());
+            }
+        }
+        self.block_idx = self.block_idx.min(self.blocks.len().saturating_sub(1));
+        if self.blocks.is_empty() && !self.is_resolved(&file) {
+            self.note = Some(format!("{} has no conflict markers (a: mark resolved, o/t: take a side)", file));
+        }
+    }
+
+    pub fn select(&mut self, idx: usize) {
+        if idx < self.files.len() && idx != self.selected_idx {
+            self.selected_idx = idx;
+            self.block_idx = 0;
+            self.content_scroll = 0;
+            self.load_file();
+        }
+    }
+
+    pub fn next_block(&mut self) {
+        if self.block_idx + 1 < self.blocks.len() {
+            self.block_idx += 1;
+        }
+    }
+
+    pub fn prev_block(&mut self) {
+        self.block_idx = self.block_idx.saturating_sub(1);
+    }
+
+    fn write_file(&self) -> anyhow::Result<()> {
+        let Some(path) = self.focus_path() else {
+            return Ok(());
+        };
+        let mut text = self.lines.join("\n");
+        if self.trailing_newline && !self.lines.is_empty() {
+            text.push('\n');
+        }
+        std::fs::write(path, text)?;
+        Ok(())
+    }
+
+    // Replaces the focused block with the picked side; the whole file when it has no blocks.
+    pub fn pick(&mut self, pick: Pick) -> anyhow::Result<String> {
+        let Some(block) = self.focus_block().cloned() else {
+            return self.take_side(pick);
+        };
+        self.lines = resolve_block(&self.lines, &block, pick);
+        self.write_file()?;
+        self.load_file();
+        if self.blocks.is_empty() {
+            return self.mark_resolved();
+        }
+        Ok(format!("{} conflict(s) left", self.blocks.len()))
+    }
+
+    fn take_side(&mut self, pick: Pick) -> anyhow::Result<String> {
+        let Some(file) = self.focus_file().cloned() else {
+            return Ok("No file selected".to_string());
+        };
+        let (stage, flag) = match pick {
+            Pick::Ours => (2, "--ours"),
+            Pick::Theirs => (3, "--theirs"),
+            Pick::Both => return Ok("Both sides can only be taken per conflict block".to_string()),
+        };
+        if git::conflict_stage_at(&self.repo_root, stage, &file).is_some() {
+            git::run_git_at("Conflict", &self.repo_root, &["checkout", flag, "--", &file])?;
+            git::run_git_at("Conflict", &self.repo_root, &["add", "--", &file])?;
+        } else {
+            git::run_git_at("Conflict", &self.repo_root, &["rm", "-q", "--", &file])?;
+        }
+        self.resolved.insert(file.clone());
+        self.load_file();
+        Ok(format!("Resolved: {}", file))
+    }
+
+    pub fn mark_resolved(&mut self) -> anyhow::Result<String> {
+        let Some(file) = self.focus_file().cloned() else {
+            return Ok("No file selected".to_string());
+        };
+        if !self.blocks.is_empty() {
+            return Ok(format!("{} still has {} conflict(s)", file, self.blocks.len()));
+        }
+        git::run_git_at("Conflict", &self.repo_root, &["add", "--", &file])?;
+        self.resolved.insert(file.clone());
+        self.note = None;
+        let left = self.files.len() - self.resolved.len();
+        Ok(format!("Resolved: {} ({} file(s) left)", file, left))
+    }
+
+    // After an external edit: reload, and stage the file once no markers remain.
+    pub fn after_edit(&mut self) -> anyhow::Result<Option<String>> {
+        self.load_file();
+        let unresolved = self.focus_file().is_some_and(|f| !self.is_resolved(f));
+        if unresolved && self.blocks.is_empty() && !self.lines.is_empty() {
+            return self.mark_resolved().map(Some);
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::system::system;
+    use crate::ui::common::TestEnv;
+
+    fn to_lines(text: &str) -> Vec<String> {
+        split_lines(text)
+    }
+
+    #[test]
+    fn test_parse_conflicts_and_resolve() {
+        let lines = to_lines("top\n<<<<<<< HEAD\nours\n||||||| base\nbase\n=======\ntheirs\n>>>>>>> topic\nbottom\n");
+        let blocks = parse_conflicts(&lines);
+        assert_eq!(blocks.len(), 1);
+        let b = &blocks[0];
+        assert_eq!((b.start, b.end), (1, 7));
+        assert_eq!((b.ours_label.as_str(), b.theirs_label.as_str()), ("HEAD", "topic"));
+        assert_eq!(b.base, Some(vec!["base".to_string()]));
+        assert_eq!(resolve_block(&lines, b, Pick::Theirs), to_lines("top\ntheirs\nbottom"));
+        assert_eq!(resolve_block(&lines, b, Pick::Both), to_lines("top\nours\ntheirs\nbottom"));
+
+        let plain = to_lines("<<<<<<< HEAD\na\n=======\nb\n>>>>>>> x\n");
+        assert_eq!(parse_conflicts(&plain)[0].base, None);
+    }
+
+    #[test]
+    fn test_conflict_ctrl_recovers_base_and_stages() {
+        let env = TestEnv::repo("test_conflict_ctrl");
+        let _ = system("git config merge.conflictStyle merge");
+        std::fs::write(env.root.join("f.txt"), "1\nbase\n2\nx\n3\n").unwrap();
+        let _ = system("git add . && git commit -q -m base");
+        let _ = system("git checkout -q -b topic");
+        std::fs::write(env.root.join("f.txt"), "1\ntheirs\n2\nx\n3\n").unwrap();
+        let _ = system("git commit -q -am topic");
+        let _ = system("git checkout -q main");
+        std::fs::write(env.root.join("f.txt"), "1\nours\n2\nx\n3\n").unwrap();
+        let _ = system("git commit -q -am main");
+        let _ = system("git merge topic");
+
+        let items = git::build_git_items().unwrap();
+        assert_eq!(items[0].label, "< Conflicted >");
+        assert_eq!(items.iter().filter(|i| i.path.as_deref() == Some("f.txt")).count(), 1);
+
+        let mut ctrl = ConflictCtrl::new(Some(Path::new("f.txt"))).unwrap();
+        assert_eq!(ctrl.blocks.len(), 1);
+        assert_eq!(ctrl.blocks[0].base, Some(vec!["base".to_string()]));
+        ctrl.pick(Pick::Both).unwrap();
+        assert!(ctrl.is_resolved("f.txt"));
+        assert_eq!(std::fs::read_to_string(env.root.join("f.txt")).unwrap(), "1\nours\ntheirs\n2\nx\n3\n");
+        assert!(git::conflicted_files_at(&ctrl.repo_root).unwrap().is_empty());
+    }
+}
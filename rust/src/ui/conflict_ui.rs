@@ -0,0 +1,221 @@
+use std::path::Path;
+
+use crossterm::event::{KeyCode, KeyEvent, MouseEvent, MouseEventKind};
+use ratatui::layout::{Constraint, Direction, Layout, Margin, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span, Text};
+use ratatui::widgets::{Block, Borders, List, ListItem, ListState, Paragraph};
+
+use crate::app::{open_in_editor, AppContext};
+use crate::system::app_log;
+use crate::ui::common::{Action, Screen, mouse_pos};
+use crate::ui::conflict_ctrl::{ConflictCtrl, Pick};
+
+pub struct ConflictState {
+    pub ctrl: ConflictCtrl,
+    pub list_state: ListState,
+    pub list_area: Option<Rect>,
+    pub content_area: Option<Rect>,
+    // (file, block start) the content was last scrolled to; scrolling is free until the focus changes.
+    pub scrolled_to: Option<(usize, usize)>,
+}
+
+fn side_lines(lines: Option<&Vec<String>>, color: Color) -> Vec<Line<'static>> {
+    match lines {
+        Some(lines) => lines
+            .iter()
+            .map(|l| Line::from(Span::styled(l.replace('\t', "    "), Style::default().fg(color))))
+            .collect(),
+        None => vec![Line::from(Span::styled("< not available >", Style::default().fg(Color::DarkGray)))],
+    }
+}
+
+impl ConflictState {
+    pub fn new(focus: Option<&Path>) -> anyhow::Result<Self> {
+        Ok(Self {
+            ctrl: ConflictCtrl::new(focus)?,
+            list_state: ListState::default(),
+            list_area: None,
+            content_area: None,
+            scrolled_to: None,
+        })
+    }
+
+    pub fn render(&mut self, f: &mut ratatui::Frame) {
+        let list_height = (self.ctrl.files.len() as u16 + 1).min(8);
+        let layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(1),
+                Constraint::Length(list_height),
+                Constraint::Percentage(35),
+                Constraint::Min(3),
+            ])
+            .split(f.size());
+
+        let title = Paragraph::new(Line::from(Span::styled(
+            " >> Conflicts (o: ours, t: theirs, b: both, n/N: next/prev block, a: mark resolved, E: editor, q: back)",
+            Style::default().fg(Color::Green).add_modifier(Modifier::BOLD),
+        )));
+        f.render_widget(title, layout[0]);
+
+        let items: Vec<ListItem> = self
+            .ctrl
+            .files
+            .iter()
+            .map(|file| {
+                if self.ctrl.is_resolved(file) {
+                    ListItem::new(format!("[resolved] {}", file)).style(Style::default().fg(Color::DarkGray))
+                } else {
+                    ListItem::new(format!("[conflict] {}", file)).style(Style::default().fg(Color::Red))
+                }
+            })
+            .collect();
+        self.list_state.select(Some(self.ctrl.selected_idx));
+        let list = List::new(items)
+            .block(Block::default().title("Conflicted files"))
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        f.render_stateful_widget(list, layout[1], &mut self.list_state);
+        self.list_area = Some(layout[1]);
+
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Ratio(1, 3), Constraint::Ratio(1, 3), Constraint::Ratio(1, 3)])
+            .split(layout[2]);
+        let count = self.ctrl.blocks.len();
+        if let Some(block) = self.ctrl.focus_block() {
+            let panes = [
+                (format!(" Ours: {} [{}/{}] ", block.ours_label, self.ctrl.block_idx + 1, count), Some(&block.ours), Color::Green),
+                (" Base ".to_string(), block.base.as_ref(), Color::Gray),
+                (format!(" Theirs: {} ", block.theirs_label), Some(&block.theirs), Color::Cyan),
+            ];
+            for ((title, lines, color), area) in panes.into_iter().zip(columns.iter()) {
+                let view = Paragraph::new(Text::from(side_lines(lines, color)))
+                    .block(Block::default().borders(Borders::TOP).title(title));
+                f.render_widget(view, *area);
+            }
+        } else {
+            let text = self.ctrl.note.clone().unwrap_or_else(|| "No conflict blocks left".to_string());
+            let view = Paragraph::new(text)
+                .style(Style::default().fg(Color::Yellow))
+                .block(Block::default().borders(Borders::TOP).title(" Conflict "));
+            f.render_widget(view, layout[2]);
+        }
+
+        // Whole file with the markers, the focused block highlighted and kept in view.
+        let focus = self.ctrl.focus_block().map(|b| (b.start, b.end));
+        let lines: Vec<Line> = self
+            .ctrl
+            .lines
+            .iter()
+            .enumerate()
+            .map(|(i, l)| {
+                let text = l.replace('\t', "    ");
+                let is_marker = ["<<<<<<<", "|||||||", "=======", ">>>>>>>"].iter().any(|m| l.starts_with(m));
+                let mut style = if is_marker { Style::default().fg(Color::Yellow) } else { Style::default() };
+                if focus.is_some_and(|(s, e)| i >= s && i <= e) {
+                    style = style.bg(Color::DarkGray);
+                }
+                Line::from(Span::styled(text, style))
+            })
+            .collect();
+        if let Some((start, _)) = focus {
+            let key = (self.ctrl.selected_idx, start);
+            if self.scrolled_to != Some(key) {
+                self.scrolled_to = Some(key);
+                self.ctrl.content_scroll = (start as u16).saturating_sub(2);
+            }
+        }
+        let name = self.ctrl.focus_file().cloned().unwrap_or_default();
+        let view = Paragraph::new(Text::from(lines))
+            .block(Block::default().borders(Borders::TOP).title(format!(" {} ", name)))
+            .scroll((self.ctrl.content_scroll, 0));
+        f.render_widget(view, layout[3]);
+        self.content_area = Some(layout[3]);
+    }
+
+    fn back(ctx: &mut AppContext) -> anyhow::Result<Action> {
+        Ok(match crate::ui::git_stage_ui::GitStageState::new(ctx) {
+            Ok(state) => Action::Switch(Screen::GitStage(Box::new(state))),
+            Err(_) => Action::Switch(Screen::Main(Box::new(crate::ui::main_ui::MainState::new(ctx)?))),
+        })
+    }
+
+    fn report(result: anyhow::Result<String>) -> Action {
+        match result {
+            Ok(msg) => Action::Toast(msg),
+            Err(err) => {
+                app_log(&format!("Conflict error: {}", err));
+                Action::Toast(format!("Failed: {}", err))
+            }
+        }
+    }
+
+    pub fn on_key(&mut self, ctx: &mut AppContext, key: KeyEvent) -> anyhow::Result<Action> {
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Self::back(ctx),
+            KeyCode::Char('j') | KeyCode::Down => self.ctrl.select(self.ctrl.selected_idx + 1),
+            KeyCode::Char('k') | KeyCode::Up => self.ctrl.select(self.ctrl.selected_idx.saturating_sub(1)),
+            KeyCode::Char('n') | KeyCode::Char(']') => self.ctrl.next_block(),
+            KeyCode::Char('N') | KeyCode::Char('[') => self.ctrl.prev_block(),
+            KeyCode::PageDown => self.ctrl.content_scroll = self.ctrl.content_scroll.saturating_add(10),
+            KeyCode::PageUp => self.ctrl.content_scroll = self.ctrl.content_scroll.saturating_sub(10),
+            KeyCode::Char('o') => return Ok(Self::report(self.ctrl.pick(Pick::Ours))),
+            KeyCode::Char('t') => return Ok(Self::report(self.ctrl.pick(Pick::Theirs))),
+            KeyCode::Char('b') => return Ok(Self::report(self.ctrl.pick(Pick::Both))),
+            KeyCode::Char('a') | KeyCode::Char('A') => return Ok(Self::report(self.ctrl.mark_resolved())),
+            KeyCode::Char('E') => {
+                if let Some(path) = self.ctrl.focus_path() {
+                    open_in_editor(&ctx.config.edit_app, &format!("\"{}\"", path.to_string_lossy()));
+                    return Ok(match self.ctrl.after_edit() {
+                        Ok(Some(msg)) => Action::Toast(msg),
+                        Ok(None) => Action::None,
+                        Err(err) => Action::Toast(format!("Failed: {}", err)),
+                    });
+                }
+            }
+            _ => {}
+        }
+        Ok(Action::None)
+    }
+
+    pub fn on_mouse(&mut self, _ctx: &mut AppContext, me: MouseEvent) -> anyhow::Result<Action> {
+        if let Some(area) = self.list_area {
+            if area.contains(mouse_pos(&me)) {
+                match me.kind {
+                    MouseEventKind::Down(_) => {
+                        let inner = area.inner(&Margin { horizontal: 0, vertical: 1 });
+                        if me.row >= inner.y && me.row < inner.y + inner.height {
+                            self.ctrl.select((me.row - inner.y) as usize + self.list_state.offset());
+                        }
+                    }
+                    MouseEventKind::ScrollDown => self.ctrl.select(self.ctrl.selected_idx + 1),
+                    MouseEventKind::ScrollUp => self.ctrl.select(self.ctrl.selected_idx.saturating_sub(1)),
+                    _ => {}
+                }
+            }
+        }
+        if let Some(area) = self.content_area {
+            if area.contains(mouse_pos(&me)) {
+                if matches!(me.kind, MouseEventKind::ScrollDown) {
+                    self.ctrl.content_scroll = self.ctrl.content_scroll.saturating_add(3);
+                } else if matches!(me.kind, MouseEventKind::ScrollUp) {
+                    self.ctrl.content_scroll = self.ctrl.content_scroll.saturating_sub(3);
+                }
+            }
+        }
+        Ok(Action::None)
+    }
+}
+
+impl crate::ui::common::ScreenState for ConflictState {
+    fn render(&mut self, f: &mut ratatui::Frame) {
+        self.render(f);
+    }
+    fn on_key(&mut self, ctx: &mut AppContext, key: KeyEvent) -> anyhow::Result<Action> {
+        self.on_key(ctx, key)
+    }
+    fn on_mouse(&mut self, ctx: &mut AppContext, me: MouseEvent) -> anyhow::Result<Action> {
+        self.on_mouse(ctx, me)
+    }
+}
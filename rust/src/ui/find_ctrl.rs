@@ -1,12 +1,15 @@
 use std::cmp::min;
 use crate::app::AppContext;
 use crate::system::system_safe;
+use crate::ui::highlight::next_generation;
 use crate::util::file_size;
 
 pub struct FindCtrl {
     pub files: Vec<String>,
     pub selected_idx: usize,
     pub content: Vec<String>,
+    // Generation of `content`, for the syntax cache.
+    pub content_gen: u64,
     pub content_scroll: u16,
 }
 
@@ -29,6 +32,7 @@ impl FindCtrl {
             files,
             selected_idx: 0,
             content: vec!["< Nothing to display >".to_string()],
+            content_gen: 0,
             content_scroll: 0,
         };
         ctrl.load_content();
@@ -66,6 +70,7 @@ impl FindCtrl {
         } else {
             self.content = vec!["< Nothing to display >".to_string()];
         }
+        self.content_gen = next_generation();
     }
 }
 
@@ -110,6 +115,7 @@ impl FindCtrl {
             files,
             selected_idx: 0,
             content: vec![],
+            content_gen: 0,
             content_scroll: 0,
         };
         ctrl.load_content();
@@ -8,8 +8,10 @@ use ratatui::text::Text;
 use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph};
 
 use crate::app::{open_in_editor, AppContext};
+use crate::ui::blame_ui::{BlameOrigin, BlameState};
 use crate::ui::common::{Action, Screen, mouse_pos, is_double_click};
 use crate::ui::find_ctrl::FindCtrl;
+use crate::ui::highlight::SyntaxCache;
 
 pub struct FindState {
     pub ctrl: FindCtrl,
@@ -17,20 +19,32 @@ pub struct FindState {
     pub list_area: Option<Rect>,
     pub content_area: Option<Rect>,
     pub last_click: Option<(Instant, usize)>,
+    pub syntax: SyntaxCache,
 }
 
 impl FindState {
     pub fn from_args(ctx: &AppContext, args: &[String]) -> anyhow::Result<Self> {
-        let ctrl = FindCtrl::from_args(ctx, args)?;
+        Ok(Self::with_ctrl(FindCtrl::from_args(ctx, args)?))
+    }
+
+    // Back from Blame without running `find` again.
+    pub fn with_files(files: Vec<String>, selected_idx: usize) -> Self {
+        let mut ctrl = FindCtrl::with_files(files);
+        ctrl.set_selected(selected_idx);
+        Self::with_ctrl(ctrl)
+    }
+
+    fn with_ctrl(ctrl: FindCtrl) -> Self {
         let mut list_state = ListState::default();
         list_state.select(Some(ctrl.selected_idx));
-        Ok(Self {
+        Self {
             ctrl,
             list_state,
             list_area: None,
             content_area: None,
             last_click: None,
-        })
+            syntax: SyntaxCache::new(),
+        }
     }
 
     pub fn render(&mut self, f: &mut ratatui::Frame) {
@@ -51,7 +65,10 @@ impl FindState {
         f.render_stateful_widget(list, layout[0], &mut self.list_state);
         self.list_area = Some(layout[0]);
 
-        let text = Text::from(self.ctrl.content.join("\n"));
+        let scroll = self.ctrl.content_scroll as usize;
+        let visible = scroll..scroll + layout[1].height as usize;
+        let path = self.ctrl.focus_file().unwrap_or_default();
+        let text = Text::from(self.syntax.file_lines(&path, self.ctrl.content_gen, &self.ctrl.content, visible));
         let view = Paragraph::new(text)
             .block(Block::default().title("Content"));
         f.render_widget(view.scroll((self.ctrl.content_scroll, 0)), layout[1]);
@@ -78,6 +95,15 @@ impl FindState {
                     open_in_editor(&ctx.config.edit_app, &file);
                 }
             }
+            KeyCode::Char('B') => {
+                if let Some(file) = self.ctrl.focus_file() {
+                    let origin = BlameOrigin::Find(self.ctrl.files.clone(), self.ctrl.selected_idx);
+                    return Ok(match BlameState::new(Path::new(&file), self.ctrl.content_scroll as usize, origin) {
+                        Ok(state) => Action::Switch(Screen::Blame(Box::new(state))),
+                        Err(err) => Action::Toast(err.to_string()),
+                    });
+                }
+            }
             _ => {}
         }
         Ok(Action::None)
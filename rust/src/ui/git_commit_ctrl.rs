@@ -1,57 +1,211 @@
 use std::cmp::min;
-use std::path::{PathBuf};
+use std::path::{Path, PathBuf};
 use crate::app::AppContext;
 use crate::git;
-use crate::system::{system_logged};
+use crate::config::{CommitOptions, CommitRules};
+use crate::ui::commit_editor::{lint_message, required_regex, CommitEditor};
+use crate::ui::highlight::next_generation;
+use crate::system::{log_command_result, system_logged};
 use crate::util::strip_ansi;
 
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum CommitKind {
+    Message,
+    AmendNoEdit,
+}
+
+// Output of a failed `git commit`, hooks included, kept for the result popup.
+pub struct CommitResult {
+    pub kind: CommitKind,
+    pub code: Option<i32>,
+    pub output: Vec<String>,
+    pub scroll: u16,
+}
+
+const CO_AUTHOR: &str = "Co-authored-by: ";
+
+// Earlier subjects offered by Up on the subject line.
+const HISTORY_LIMIT: usize = 200;
+
 pub struct GitCommitCtrl {
-    pub message: String,
+    pub editor: CommitEditor,
+    pub preview: bool,
     pub files: Vec<String>,
     pub selected_idx: usize,
     pub content: Vec<String>,
+    // Generation of `content`, for the syntax cache.
+    pub content_gen: u64,
     pub commits: Vec<String>,
     pub content_scroll: u16,
     pub input_mode: bool,
     pub repo_root: PathBuf,
+    pub side_by_side: bool,
+    // Set while amending: the commit HEAD's changes are diffed against.
+    pub amend_base: Option<String>,
+    pub pushed_to: Option<String>,
+    draft: Option<CommitEditor>,
+    pub prefixes: Vec<String>,
+    // Selected row while the prefix picker is open.
+    pub prefix_picker: Option<usize>,
+    history: Option<Vec<String>>,
+    history_idx: Option<usize>,
+    history_draft: CommitEditor,
+    pub result: Option<CommitResult>,
+    pub rules: CommitRules,
+    // Compiled `rules.required_pattern`, so linting on every frame does not rebuild it.
+    required_re: Option<regex::Regex>,
+    pub branch: String,
+    // Message the lint warning was shown for; committing it again goes ahead.
+    lint_confirmed: Option<String>,
+    pub options: CommitOptions,
+    // "Name <email>" of everyone in the recent history, for the co-author picker.
+    pub authors: Vec<String>,
+    pub coauthor_picker: Option<usize>,
 }
 
 impl GitCommitCtrl {
-    pub fn new(_ctx: &AppContext) -> anyhow::Result<Self> {
+    pub fn new(ctx: &AppContext) -> anyhow::Result<Self> {
         let repo_root = git::repo_root()?;
-        let staged = system_logged(
-            "GitCommit",
-            &git::git_cmd_at(&repo_root, "diff --name-only --staged"),
-        )?;
-        let mut files = Vec::new();
-        for line in staged.lines() {
-            if !line.trim().is_empty() {
-                files.push(format!("s {}", line));
-            }
-        }
-        if files.is_empty() {
-            files.push("< Nothing >".to_string());
-        }
+        let files = Self::staged_files(&repo_root, None)?;
         let commits =
             git::commit_list_at(&repo_root).unwrap_or_else(|_| vec!["< There is no commit >".to_string()]);
-        Self::with_data(repo_root, files, commits)
+        let mut ctrl = Self::with_data(repo_root, files, commits)?;
+        ctrl.side_by_side = ctx.config.diff_side_by_side;
+        ctrl.prefixes = ctx.config.commit_prefixes.clone();
+        ctrl.set_rules(ctx.config.commit_rules_for(&ctrl.repo_root));
+        ctrl.branch = git::get_current_branch().unwrap_or_default();
+        ctrl.options = ctx.config.commit_options_for(&ctrl.repo_root);
+        if let Some(template) = git::commit_template_at(&ctrl.repo_root) {
+            ctrl.editor = CommitEditor::from_message(&template);
+        }
+        Ok(ctrl)
     }
 
     pub fn with_data(repo_root: PathBuf, files: Vec<String>, commits: Vec<String>) -> anyhow::Result<Self> {
         let mut ctrl = Self {
-            message: String::new(),
+            editor: CommitEditor::new(),
+            preview: false,
             files,
             selected_idx: 0,
             content: vec!["< Nothing to display >".to_string()],
+            content_gen: 0,
             commits,
             content_scroll: 0,
             input_mode: true,
             repo_root,
+            side_by_side: false,
+            amend_base: None,
+            pushed_to: None,
+            draft: None,
+            prefixes: Vec::new(),
+            prefix_picker: None,
+            history: None,
+            history_idx: None,
+            history_draft: CommitEditor::default(),
+            result: None,
+            rules: CommitRules::default(),
+            required_re: None,
+            branch: String::new(),
+            lint_confirmed: None,
+            options: CommitOptions::default(),
+            authors: Vec::new(),
+            coauthor_picker: None,
         };
         let _ = ctrl.load_content();
         Ok(ctrl)
     }
 
+    fn staged_files(root: &Path, base: Option<&str>) -> anyhow::Result<Vec<String>> {
+        let cmd = match base {
+            Some(base) => format!("diff --name-only --staged {}", base),
+            None => "diff --name-only --staged".to_string(),
+        };
+        let staged = system_logged("GitCommit", &git::git_cmd_at(root, &cmd))?;
+        let mut files = Vec::new();
+        for line in staged.lines() {
+            if !line.trim().is_empty() {
+                files.push(format!("s {}", line));
+            }
+        }
+        if files.is_empty() {
+            files.push("< Nothing >".to_string());
+        }
+        Ok(files)
+    }
+
+    pub fn is_amend(&self) -> bool {
+        self.amend_base.is_some()
+    }
+
+    // Amend mode loads HEAD's message in place of the draft and diffs HEAD plus the index;
+    // leaving it brings the draft back.
+    pub fn set_amend(&mut self, on: bool) -> anyhow::Result<()> {
+        if on == self.is_amend() {
+            return Ok(());
+        }
+        if on {
+            let message = git::head_message_at(&self.repo_root)?;
+            self.amend_base = Some(git::amend_base_at(&self.repo_root));
+            self.draft = Some(std::mem::replace(&mut self.editor, CommitEditor::from_message(&message)));
+            self.pushed_to = git::head_pushed_to();
+        } else {
+            self.amend_base = None;
+            self.editor = self.draft.take().unwrap_or_default();
+            self.pushed_to = None;
+        }
+        self.files = Self::staged_files(&self.repo_root, self.amend_base.as_deref())?;
+        self.selected_idx = 0;
+        self.content_scroll = 0;
+        self.load_content()
+    }
+
+    // Up on the subject line steps back through this repo's earlier messages, newest first.
+    pub fn history_prev(&mut self) {
+        let root = &self.repo_root;
+        let history = self.history.get_or_insert_with(|| {
+            let mut messages: Vec<String> = Vec::new();
+            for c in git::commit_history_at(root, HISTORY_LIMIT).unwrap_or_default() {
+                let message = c.message.trim_end().to_string();
+                if !messages.contains(&message) {
+                    messages.push(message);
+                }
+            }
+            messages
+        });
+        let next = self.history_idx.map_or(0, |i| i + 1);
+        let Some(message) = history.get(next) else {
+            return;
+        };
+        let recalled = CommitEditor::from_message(message);
+        let previous = std::mem::replace(&mut self.editor, recalled);
+        if self.history_idx.is_none() {
+            self.history_draft = previous;
+        }
+        self.history_idx = Some(next);
+    }
+
+    // Returns false when not browsing the history, so Down can move the cursor instead.
+    pub fn history_next(&mut self) -> bool {
+        let Some(idx) = self.history_idx else {
+            return false;
+        };
+        if idx == 0 {
+            self.history_idx = None;
+            self.editor = std::mem::take(&mut self.history_draft);
+        } else if let Some(message) = self.history.as_ref().and_then(|h| h.get(idx - 1)) {
+            self.history_idx = Some(idx - 1);
+            self.editor = CommitEditor::from_message(message);
+        }
+        true
+    }
+
+    pub fn apply_prefix(&mut self, idx: usize) {
+        if let Some(prefix) = self.prefixes.get(idx) {
+            self.editor.set_prefix(prefix, &self.prefixes);
+        }
+        self.prefix_picker = None;
+    }
+
     pub fn focus_file_name(&self) -> Option<String> {
         let line = self.files.get(self.selected_idx)?.clone();
         let parts: Vec<&str> = line.splitn(2, ' ').collect();
@@ -70,6 +224,7 @@ impl GitCommitCtrl {
                 .arg("diff")
                 .arg("--color")
                 .arg("--staged")
+                .args(self.amend_base.iter())
                 .arg("--")
                 .arg(&name)
                 .output();
@@ -95,9 +250,156 @@ impl GitCommitCtrl {
         } else {
             self.content = vec!["< Nothing to display >".to_string()];
         }
+        self.content_gen = next_generation();
         Ok(())
     }
 
+    // Returns false when the history has no authors to offer.
+    pub fn open_coauthor_picker(&mut self) -> bool {
+        if self.authors.is_empty() {
+            for c in git::commit_history_at(&self.repo_root, HISTORY_LIMIT).unwrap_or_default() {
+                let author = format!("{} <{}>", c.author, c.email);
+                if !self.authors.contains(&author) {
+                    self.authors.push(author);
+                }
+            }
+        }
+        if self.authors.is_empty() {
+            return false;
+        }
+        self.coauthor_picker = Some(0);
+        true
+    }
+
+    pub fn is_coauthor(&self, author: &str) -> bool {
+        self.options.trailers.iter().any(|t| t.strip_prefix(CO_AUTHOR) == Some(author))
+    }
+
+    pub fn toggle_coauthor(&mut self, idx: usize) {
+        let Some(author) = self.authors.get(idx) else {
+            return;
+        };
+        let trailer = format!("{}{}", CO_AUTHOR, author);
+        if let Some(pos) = self.options.trailers.iter().position(|t| *t == trailer) {
+            self.options.trailers.remove(pos);
+        } else {
+            self.options.trailers.push(trailer);
+        }
+    }
+
+    pub fn set_rules(&mut self, rules: CommitRules) {
+        self.required_re = required_regex(&rules);
+        self.rules = rules;
+    }
+
+    pub fn violations(&self) -> Vec<String> {
+        lint_message(&self.rules, self.required_re.as_ref(), &self.editor.message(), &self.branch)
+    }
+
+    // A toast when the message may not be committed yet: blocking rules refuse it,
+    // otherwise the first attempt warns and a second one with the same message goes ahead.
+    pub fn lint_gate(&mut self) -> Option<String> {
+        let violations = self.violations();
+        if violations.is_empty() {
+            return None;
+        }
+        if self.rules.block == Some(true) {
+            return Some(format!("Commit blocked: {}", violations.join("; ")));
+        }
+        let message = self.editor.message();
+        if self.lint_confirmed.as_deref() == Some(message.as_str()) {
+            return None;
+        }
+        self.lint_confirmed = Some(message);
+        Some(format!("{} (Ctrl+S again to commit anyway)", violations.join("; ")))
+    }
+
+    fn commit_args(&self, kind: CommitKind) -> Vec<String> {
+        let mut args = vec!["commit".to_string()];
+        match kind {
+            CommitKind::Message => {
+                args.push("-m".to_string());
+                args.push(self.editor.message());
+                if self.is_amend() {
+                    args.push("--amend".to_string());
+                }
+            }
+            CommitKind::AmendNoEdit => {
+                args.push("--amend".to_string());
+                args.push("--no-edit".to_string());
+            }
+        }
+        if self.options.signoff {
+            args.push("--signoff".to_string());
+        }
+        if self.options.sign {
+            args.push("-S".to_string());
+        }
+        if kind == CommitKind::Message {
+            for trailer in &self.options.trailers {
+                args.push(format!("--trailer={}", trailer));
+            }
+        }
+        args
+    }
+
+    // Runs `git commit` with its output captured so hooks can't draw over the screen.
+    // Returns false on failure, leaving the output in `result`. A signed commit keeps stdin
+    // so pinentry can ask for a passphrase; the caller hands it the terminal.
+    pub fn commit(&mut self, kind: CommitKind, no_verify: bool) -> anyhow::Result<bool> {
+        let mut args = self.commit_args(kind);
+        if no_verify {
+            args.push("--no-verify".to_string());
+        }
+        let stdin = if self.options.sign { std::process::Stdio::inherit() } else { std::process::Stdio::null() };
+        let out = std::process::Command::new("git")
+            .arg("-C")
+            .arg(&self.repo_root)
+            .args(&args)
+            .stdin(stdin)
+            .output()?;
+        let mut text = String::from_utf8_lossy(&out.stdout).to_string();
+        text.push_str(&String::from_utf8_lossy(&out.stderr));
+        let text = strip_ansi(&text).replace('\t', "    ");
+        let logged = if out.status.success() {
+            Ok(text.clone())
+        } else {
+            Err(std::io::Error::other(text.clone()))
+        };
+        log_command_result(
+            "GitCommit",
+            &format!("git commit kind={:?} no_verify={}", kind, no_verify),
+            &logged,
+        );
+
+        if out.status.success() {
+            self.result = None;
+            // Co-authors are picked per commit, not carried on to the next one.
+            if kind == CommitKind::Message {
+                self.options.trailers.retain(|t| !t.starts_with(CO_AUTHOR));
+            }
+            return Ok(true);
+        }
+        let mut output: Vec<String> = text.lines().map(|l| l.to_string()).collect();
+        if output.is_empty() {
+            output.push("< no output >".to_string());
+        }
+        self.result = Some(CommitResult {
+            kind,
+            code: out.status.code(),
+            output,
+            scroll: 0,
+        });
+        Ok(false)
+    }
+
+    pub fn retry(&mut self, no_verify: bool) -> anyhow::Result<bool> {
+        let Some(kind) = self.result.as_ref().map(|r| r.kind) else {
+            return Ok(false);
+        };
+        self.commit(kind, no_verify)
+    }
+
     pub fn next(&mut self) -> anyhow::Result<()> {
         if !self.files.is_empty() {
             self.selected_idx = min(self.selected_idx + 1, self.files.len().saturating_sub(1));
@@ -126,6 +428,8 @@ impl GitCommitCtrl {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::system::system;
+    use crate::ui::common::TestEnv;
 
     #[test]
     fn test_git_commit_ctrl_basic() {
@@ -143,4 +447,129 @@ mod tests {
         ctrl.prev().unwrap();
         assert_eq!(ctrl.selected_idx, 0);
     }
+
+    #[test]
+    fn test_git_commit_ctrl_lint_gate() {
+        let mut ctrl = GitCommitCtrl::with_data(PathBuf::from("."), vec![], vec![]).unwrap();
+        ctrl.set_rules(CommitRules {
+            forbidden_words: Some(vec!["WIP".to_string()]),
+            required_pattern: Some("arser".to_string()),
+            ..Default::default()
+        });
+        ctrl.editor.set_subject("Lexer");
+        assert!(ctrl.violations()[0].contains("/arser/"));
+        ctrl.editor.set_subject("WIP parser");
+        assert!(ctrl.lint_gate().is_some());
+        assert!(ctrl.lint_gate().is_none());
+
+        ctrl.rules.block = Some(true);
+        assert!(ctrl.lint_gate().unwrap().starts_with("Commit blocked"));
+        ctrl.editor.set_subject("Parser");
+        assert!(ctrl.lint_gate().is_none());
+    }
+
+    #[test]
+    fn test_git_commit_ctrl_amend() {
+        let env = TestEnv::repo("test_git_commit_ctrl_amend");
+        std::fs::write(env.root.join("file1"), "one\n").unwrap();
+        let _ = system("git add file1");
+        let _ = system("git commit -q -m \"First subject\" -m \"First body\"");
+        std::fs::write(env.root.join("file2"), "two\n").unwrap();
+        let _ = system("git add file2");
+
+        let root = git::repo_root().unwrap();
+        let mut ctrl = GitCommitCtrl::with_data(root, vec![], vec![]).unwrap();
+        ctrl.editor.insert_char('x');
+        ctrl.set_amend(true).unwrap();
+        assert_eq!(ctrl.editor.subject(), "First subject");
+        assert_eq!(ctrl.editor.body(), &["First body".to_string()]);
+        assert_eq!(ctrl.files, vec!["s file1".to_string(), "s file2".to_string()]);
+        assert!(ctrl.pushed_to.is_none());
+
+        assert!(ctrl.commit(CommitKind::AmendNoEdit, false).unwrap());
+        assert_eq!(system("git rev-list --count HEAD").unwrap(), "1");
+        assert_eq!(git::head_message_at(&ctrl.repo_root).unwrap(), "First subject\n\nFirst body");
+        assert!(system("git show --name-only --format= HEAD").unwrap().contains("file2"));
+
+        ctrl.set_amend(false).unwrap();
+        assert_eq!(ctrl.editor.subject(), "x");
+    }
+
+    #[test]
+    fn test_git_commit_ctrl_history_and_template() {
+        let env = TestEnv::repo("test_git_commit_ctrl_history");
+        for (i, message) in ["-m first", "-m second -m 'second body'", "-m first"].iter().enumerate() {
+            std::fs::write(env.root.join("file1"), format!("{}\n", i)).unwrap();
+            let _ = system(&format!("git add file1 && git commit -q {}", message));
+        }
+        std::fs::write(env.root.join("template.txt"), "# comment\nfeat: \n\nWhy:\n").unwrap();
+        let _ = system("git config commit.template template.txt");
+
+        let root = git::repo_root().unwrap();
+        assert_eq!(git::commit_template_at(&root).as_deref(), Some("feat: \n\nWhy:"));
+
+        let mut ctrl = GitCommitCtrl::with_data(root, vec![], vec![]).unwrap();
+        ctrl.editor.insert_char('d');
+        ctrl.editor.newline();
+        ctrl.editor.insert_char('b');
+        ctrl.editor.toggle_section();
+        ctrl.history_prev();
+        assert_eq!(ctrl.editor.message(), "first");
+        ctrl.history_prev();
+        assert_eq!(ctrl.editor.message(), "second\n\nsecond body");
+        assert!(ctrl.editor.in_subject());
+        ctrl.history_prev();
+        assert_eq!(ctrl.editor.subject(), "second");
+        assert!(ctrl.history_next());
+        assert!(ctrl.history_next());
+        assert_eq!(ctrl.editor.message(), "d\n\nb");
+        assert!(!ctrl.history_next());
+    }
+
+    #[test]
+    fn test_git_commit_ctrl_captures_hook_failure() {
+        let env = TestEnv::repo("test_git_commit_ctrl_hook");
+        let hook = env.root.join(".git/hooks/pre-commit");
+        std::fs::write(&hook, "#!/bin/sh\necho lint failed: bad.rs\nexit 1\n").unwrap();
+        let _ = system(&format!("chmod +x {}", hook.display()));
+        std::fs::write(env.root.join("file1"), "one\n").unwrap();
+        let _ = system("git add file1");
+
+        let root = git::repo_root().unwrap();
+        let mut ctrl = GitCommitCtrl::with_data(root, vec![], vec![]).unwrap();
+        ctrl.editor.set_subject("Add file1");
+        assert!(!ctrl.commit(CommitKind::Message, false).unwrap());
+        let result = ctrl.result.as_ref().unwrap();
+        assert_eq!(result.code, Some(1));
+        assert!(result.output.iter().any(|l| l.contains("lint failed: bad.rs")));
+
+        assert!(ctrl.retry(true).unwrap());
+        assert!(ctrl.result.is_none());
+        assert_eq!(git::head_message_at(&ctrl.repo_root).unwrap(), "Add file1");
+    }
+
+    #[test]
+    fn test_git_commit_ctrl_signoff_and_coauthor() {
+        let env = TestEnv::repo("test_git_commit_ctrl_signoff");
+        std::fs::write(env.root.join("file1"), "one\n").unwrap();
+        let _ = system("git add file1 && git commit -q -m first --author=\"Alice <alice@example.com>\"");
+        std::fs::write(env.root.join("file1"), "two\n").unwrap();
+        let _ = system("git add file1");
+
+        let root = git::repo_root().unwrap();
+        let mut ctrl = GitCommitCtrl::with_data(root, vec![], vec![]).unwrap();
+        assert!(ctrl.open_coauthor_picker());
+        assert_eq!(ctrl.authors, vec!["Alice <alice@example.com>".to_string()]);
+        ctrl.toggle_coauthor(0);
+        assert!(ctrl.is_coauthor("Alice <alice@example.com>"));
+        ctrl.options.signoff = true;
+        ctrl.editor.set_subject("second");
+        assert!(ctrl.commit(CommitKind::Message, false).unwrap());
+
+        let message = git::head_message_at(&ctrl.repo_root).unwrap();
+        assert!(message.contains("Signed-off-by: tester <tester@example.com>"));
+        assert!(message.contains("Co-authored-by: Alice <alice@example.com>"));
+        assert!(!ctrl.is_coauthor("Alice <alice@example.com>"));
+        assert!(ctrl.options.signoff);
+    }
 }
@@ -1,16 +1,20 @@
 use std::time::Instant;
 
-use crossterm::event::{KeyCode, KeyEvent, MouseEvent, MouseEventKind};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseEvent, MouseEventKind};
 use ratatui::layout::{Constraint, Direction, Layout, Margin, Rect};
 use ratatui::style::{Color, Modifier, Style};
-use ratatui::text::{Text};
-use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph};
+use ratatui::text::{Line, Span, Text};
+use ratatui::widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph};
 
 use crate::app::AppContext;
 use crate::git;
 use crate::system::{app_log, system, system_stream};
-use crate::ui::common::{Action, Screen, mouse_pos, is_double_click, format_diff_lines, with_terminal_pause};
-use crate::ui::git_commit_ctrl::GitCommitCtrl;
+use crate::ui::common::{Action, Screen, centered_rect, mouse_pos, is_double_click, format_diff_side_by_side, toggle_side_by_side, with_terminal_pause};
+use crate::ui::commit_editor::{ruler_line, BODY_LIMIT, SUBJECT_LIMIT};
+use crate::ui::git_commit_ctrl::{CommitKind, GitCommitCtrl};
+use crate::ui::highlight::SyntaxCache;
+
+const BODY_ROWS: usize = 6;
 
 pub struct GitCommitState {
     pub ctrl: GitCommitCtrl,
@@ -18,6 +22,8 @@ pub struct GitCommitState {
     pub file_area: Option<Rect>,
     pub content_area: Option<Rect>,
     pub last_click: Option<(Instant, usize)>,
+    pub syntax: SyntaxCache,
+    pub body_scroll: usize,
 }
 
 impl GitCommitState {
@@ -31,6 +37,8 @@ impl GitCommitState {
             file_area: None,
             content_area: None,
             last_click: None,
+            syntax: SyntaxCache::new(),
+            body_scroll: 0,
         })
     }
 
@@ -38,7 +46,9 @@ impl GitCommitState {
         let layout = Layout::default()
             .direction(Direction::Vertical)
             .constraints([
-                Constraint::Length(3),
+                Constraint::Length(2),
+                Constraint::Length(BODY_ROWS as u16 + 1),
+                Constraint::Length(1),
                 Constraint::Length(8),
                 Constraint::Length(5),
                 Constraint::Length(1),
@@ -46,26 +56,84 @@ impl GitCommitState {
             ])
             .split(f.size());
 
-        let input_title = if self.ctrl.input_mode {
-            "Commit message (input)"
-        } else {
-            "Commit message"
-        };
-        let prompt = "Input commit message => ";
-        let input = Paragraph::new(format!("{}{}", prompt, self.ctrl.message))
-            .block(Block::default().title(input_title));
-        f.render_widget(input, layout[0]);
+        let editor = &self.ctrl.editor;
+        let mode = if self.ctrl.input_mode { " (input)" } else { "" };
+        let subject_title = format!(
+            "Subject{} {}/{}",
+            mode,
+            editor.subject().chars().count(),
+            SUBJECT_LIMIT
+        );
+        let mut title_spans = vec![Span::raw(subject_title)];
+        if self.ctrl.is_amend() {
+            title_spans.push(Span::styled(" [amend]", Style::default().fg(Color::Yellow)));
+        }
+        let options = &self.ctrl.options;
+        let mut flags = Vec::new();
+        if options.signoff {
+            flags.push("signoff".to_string());
+        }
+        if options.sign {
+            flags.push("sign".to_string());
+        }
+        if !options.trailers.is_empty() {
+            flags.push(format!("trailers: {}", options.trailers.len()));
+        }
+        if !flags.is_empty() {
+            title_spans.push(Span::styled(format!(" [{}]", flags.join(", ")), Style::default().fg(Color::Cyan)));
+        }
+        if let Some(upstream) = &self.ctrl.pushed_to {
+            title_spans.push(Span::styled(
+                format!(" HEAD is already pushed to {}", upstream),
+                Style::default().fg(Color::Red),
+            ));
+        }
+        let subject = Paragraph::new(ruler_line(editor.subject(), SUBJECT_LIMIT))
+            .block(Block::default().title(Line::from(title_spans)));
+        f.render_widget(subject, layout[0]);
+
+        if editor.row > 0 {
+            let body_row = editor.row - 1;
+            if body_row < self.body_scroll {
+                self.body_scroll = body_row;
+            } else if body_row >= self.body_scroll + BODY_ROWS {
+                self.body_scroll = body_row + 1 - BODY_ROWS;
+            }
+        }
+        let body_lines: Vec<Line> = editor
+            .body()
+            .iter()
+            .skip(self.body_scroll)
+            .take(BODY_ROWS)
+            .map(|l| ruler_line(l, BODY_LIMIT))
+            .collect();
+        let body_title = "Body (Enter: newline, Tab: subject/body, Ctrl+P: preview, Ctrl+S: commit)";
+        let body = Paragraph::new(Text::from(body_lines)).block(Block::default().title(body_title));
+        f.render_widget(body, layout[1]);
+
+        let violations = self.ctrl.violations();
+        if !violations.is_empty() {
+            let color = if self.ctrl.rules.block == Some(true) { Color::Red } else { Color::Yellow };
+            let lint = Paragraph::new(format!("Lint: {}", violations.join("; ")))
+                .style(Style::default().fg(color));
+            f.render_widget(lint, layout[2]);
+        }
+
         if self.ctrl.input_mode {
-            let inner = layout[0].inner(&Margin {
+            let (area, y) = if editor.row == 0 {
+                (layout[0], 0)
+            } else {
+                (layout[1], (editor.row - 1 - self.body_scroll) as u16)
+            };
+            let inner = area.inner(&Margin {
                 horizontal: 0,
                 vertical: 1,
             });
             let cursor_x = inner
                 .x
-                .saturating_add(prompt.len() as u16)
-                .saturating_add(self.ctrl.message.len() as u16)
+                .saturating_add(editor.cursor_width() as u16)
                 .min(inner.x + inner.width.saturating_sub(1));
-            let cursor_y = inner.y.min(inner.y + inner.height.saturating_sub(1));
+            let cursor_y = inner.y.saturating_add(y).min(inner.y + inner.height.saturating_sub(1));
             f.set_cursor(cursor_x, cursor_y);
         }
 
@@ -74,8 +142,8 @@ impl GitCommitState {
         let file_list = List::new(file_items)
             .block(Block::default().title("Files"))
             .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
-        f.render_stateful_widget(file_list, layout[1], &mut self.list_state);
-        self.file_area = Some(layout[1]);
+        f.render_stateful_widget(file_list, layout[3], &mut self.list_state);
+        self.file_area = Some(layout[3]);
 
         let commit_items: Vec<ListItem> = self.ctrl
             .commits
@@ -84,20 +152,229 @@ impl GitCommitState {
             .collect();
         let commit_list = List::new(commit_items)
             .block(Block::default().title("Commits"));
-        f.render_widget(commit_list, layout[2]);
+        f.render_widget(commit_list, layout[4]);
 
-        let separator = "-".repeat(layout[3].width as usize);
+        let separator = "-".repeat(layout[5].width as usize);
         let sep = Paragraph::new(separator).style(Style::default().fg(Color::DarkGray));
-        f.render_widget(sep, layout[3]);
+        f.render_widget(sep, layout[5]);
+
+        if self.ctrl.preview {
+            let lines: Vec<Line> = self.ctrl.editor.wrapped_preview().into_iter().map(Line::from).collect();
+            let view = Paragraph::new(Text::from(lines))
+                .block(Block::default().title(format!("Preview (body wrapped at {})", BODY_LIMIT)));
+            f.render_widget(view, layout[6]);
+        } else {
+            let scroll = self.ctrl.content_scroll as usize;
+            let visible = scroll..scroll + layout[6].height as usize;
+            let diff_lines = if self.ctrl.side_by_side {
+                format_diff_side_by_side(&self.ctrl.content, layout[6].width)
+            } else {
+                self.syntax.diff_lines(self.ctrl.content_gen, &self.ctrl.content, layout[6].width, visible)
+            };
+            let view = Paragraph::new(Text::from(diff_lines)).block(Block::default().title("Diff"));
+            f.render_widget(view.scroll((self.ctrl.content_scroll, 0)), layout[6]);
+        }
+        self.content_area = Some(layout[6]);
+
+        // Overlays go on top of either content view; on_key routes keys to them first.
+        if let Some(result) = &mut self.ctrl.result {
+            let area = centered_rect(80, 70, f.size());
+            f.render_widget(Clear, area);
+            let code = result.code.map_or("signal".to_string(), |c| c.to_string());
+            let title = format!(
+                " git commit failed (exit {}) - r: retry, n: retry --no-verify, e/Esc: back to edit ",
+                code
+            );
+            let inner_height = area.height.saturating_sub(2);
+            let max_scroll = (result.output.len() as u16).saturating_sub(inner_height);
+            result.scroll = result.scroll.min(max_scroll);
+            let lines: Vec<Line> = result.output.iter().map(|l| Line::from(l.as_str())).collect();
+            let view = Paragraph::new(Text::from(lines))
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title(title)
+                        .border_style(Style::default().fg(Color::Red)),
+                )
+                .scroll((result.scroll, 0));
+            f.render_widget(view, area);
+        }
+
+        if let Some(selected) = self.ctrl.coauthor_picker {
+            let area = centered_rect(50, 50, f.size());
+            f.render_widget(Clear, area);
+            let items: Vec<ListItem> = self
+                .ctrl
+                .authors
+                .iter()
+                .map(|a| {
+                    let mark = if self.ctrl.is_coauthor(a) { "[x]" } else { "[ ]" };
+                    ListItem::new(format!("{} {}", mark, a))
+                })
+                .collect();
+            let list = List::new(items)
+                .block(Block::default().borders(Borders::ALL).title(" Co-authored-by (Space: toggle, Esc: close) "))
+                .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+            let mut state = ListState::default();
+            state.select(Some(selected));
+            f.render_stateful_widget(list, area, &mut state);
+        }
 
-        let diff_lines = format_diff_lines(&self.ctrl.content, layout[4].width);
-        let view = Paragraph::new(Text::from(diff_lines)).block(Block::default().title("Diff"));
-        f.render_widget(view.scroll((self.ctrl.content_scroll, 0)), layout[4]);
-        self.content_area = Some(layout[4]);
+        if let Some(selected) = self.ctrl.prefix_picker {
+            let area = centered_rect(30, 40, f.size());
+            f.render_widget(Clear, area);
+            let items: Vec<ListItem> = self.ctrl.prefixes.iter().map(|p| ListItem::new(p.as_str())).collect();
+            let list = List::new(items)
+                .block(Block::default().borders(Borders::ALL).title(" Prefix "))
+                .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+            let mut state = ListState::default();
+            state.select(Some(selected));
+            f.render_stateful_widget(list, area, &mut state);
+        }
+    }
+
+    // Signing may prompt through pinentry, which needs the terminal back while git runs.
+    // A commit clears the picked co-authors, so the options are saved again.
+    fn run_commit(&mut self, ctx: &mut AppContext, f: impl FnOnce(&mut GitCommitCtrl) -> anyhow::Result<bool>) -> anyhow::Result<bool> {
+        let mut committed = false;
+        if self.ctrl.options.sign {
+            with_terminal_pause(|| {
+                committed = f(&mut self.ctrl)?;
+                Ok(())
+            })?;
+        } else {
+            committed = f(&mut self.ctrl)?;
+        }
+        if committed {
+            self.save_options(ctx)?;
+        }
+        Ok(committed)
+    }
+
+    fn save_options(&self, ctx: &mut AppContext) -> anyhow::Result<()> {
+        ctx.config.set_commit_options(&self.ctrl.repo_root, self.ctrl.options.clone());
+        ctx.save()
     }
 
     pub fn on_key(&mut self, ctx: &mut AppContext, key: KeyEvent) -> anyhow::Result<Action> {
+        if let Some(result) = &mut self.ctrl.result {
+            match key.code {
+                KeyCode::Char('r') | KeyCode::Char('n') => {
+                    let no_verify = key.code == KeyCode::Char('n');
+                    let committed = self.run_commit(ctx, |ctrl| ctrl.retry(no_verify))?;
+                    if committed {
+                        return Ok(Action::Switch(Screen::Main(Box::new(crate::ui::main_ui::MainState::new(ctx)?))));
+                    }
+                }
+                KeyCode::Char('e') | KeyCode::Esc => {
+                    self.ctrl.result = None;
+                    self.ctrl.input_mode = true;
+                }
+                KeyCode::Char('j') | KeyCode::Down => result.scroll = result.scroll.saturating_add(1),
+                KeyCode::Char('k') | KeyCode::Up => result.scroll = result.scroll.saturating_sub(1),
+                KeyCode::PageDown => result.scroll = result.scroll.saturating_add(10),
+                KeyCode::PageUp => result.scroll = result.scroll.saturating_sub(10),
+                _ => {}
+            }
+            return Ok(Action::None);
+        }
+
+        if let Some(selected) = self.ctrl.prefix_picker {
+            let last = self.ctrl.prefixes.len().saturating_sub(1);
+            match key.code {
+                KeyCode::Esc => self.ctrl.prefix_picker = None,
+                KeyCode::Down | KeyCode::Char('j') => self.ctrl.prefix_picker = Some((selected + 1).min(last)),
+                KeyCode::Up | KeyCode::Char('k') => self.ctrl.prefix_picker = Some(selected.saturating_sub(1)),
+                KeyCode::Enter => {
+                    self.ctrl.apply_prefix(selected);
+                    self.ctrl.input_mode = true;
+                }
+                _ => {}
+            }
+            return Ok(Action::None);
+        }
+
+        if let Some(selected) = self.ctrl.coauthor_picker {
+            let last = self.ctrl.authors.len().saturating_sub(1);
+            match key.code {
+                KeyCode::Esc => self.ctrl.coauthor_picker = None,
+                KeyCode::Down | KeyCode::Char('j') => self.ctrl.coauthor_picker = Some((selected + 1).min(last)),
+                KeyCode::Up | KeyCode::Char('k') => self.ctrl.coauthor_picker = Some(selected.saturating_sub(1)),
+                KeyCode::Char(' ') | KeyCode::Enter => {
+                    self.ctrl.toggle_coauthor(selected);
+                    self.save_options(ctx)?;
+                }
+                _ => {}
+            }
+            return Ok(Action::None);
+        }
+
+        let ctrl_key = key.modifiers.contains(KeyModifiers::CONTROL);
+        if ctrl_key && key.code == KeyCode::Char('o') {
+            self.ctrl.options.signoff = !self.ctrl.options.signoff;
+            self.save_options(ctx)?;
+            return Ok(Action::None);
+        }
+        if ctrl_key && key.code == KeyCode::Char('g') {
+            self.ctrl.options.sign = !self.ctrl.options.sign;
+            self.save_options(ctx)?;
+            return Ok(Action::None);
+        }
+        if ctrl_key && key.code == KeyCode::Char('r') {
+            if !self.ctrl.open_coauthor_picker() {
+                return Ok(Action::Toast("No authors found in the history".to_string()));
+            }
+            return Ok(Action::None);
+        }
+        if ctrl_key && key.code == KeyCode::Char('t') {
+            if self.ctrl.prefixes.is_empty() {
+                return Ok(Action::Toast("No commitPrefixes configured".to_string()));
+            }
+            self.ctrl.prefix_picker = Some(0);
+            return Ok(Action::None);
+        }
+        if ctrl_key && key.code == KeyCode::Char('s') {
+            if self.ctrl.editor.is_empty() {
+                return Ok(Action::Toast("Commit subject is empty".to_string()));
+            }
+            if let Some(msg) = self.ctrl.lint_gate() {
+                return Ok(Action::Toast(msg));
+            }
+            if self.run_commit(ctx, |ctrl| ctrl.commit(CommitKind::Message, false))? {
+                return Ok(Action::Switch(Screen::Main(Box::new(crate::ui::main_ui::MainState::new(ctx)?))));
+            }
+            return Ok(Action::None);
+        }
+        if ctrl_key && key.code == KeyCode::Char('a') {
+            if let Err(err) = self.ctrl.set_amend(!self.ctrl.is_amend()) {
+                app_log(&format!("Amend toggle error: {}", err));
+                return Ok(Action::Toast(format!("Cannot toggle amend: {}", err)));
+            }
+            self.syntax = SyntaxCache::new();
+            if let Some(upstream) = &self.ctrl.pushed_to {
+                return Ok(Action::Toast(format!(
+                    "HEAD is already in {}; amending will need a force push",
+                    upstream
+                )));
+            }
+            return Ok(Action::None);
+        }
+        if ctrl_key && key.code == KeyCode::Char('n') {
+            if !self.ctrl.is_amend() {
+                return Ok(Action::Toast("Press Ctrl+A to enter amend mode first".to_string()));
+            }
+            if self.run_commit(ctx, |ctrl| ctrl.commit(CommitKind::AmendNoEdit, false))? {
+                return Ok(Action::Switch(Screen::Main(Box::new(crate::ui::main_ui::MainState::new(ctx)?))));
+            }
+            return Ok(Action::None);
+        }
+        if ctrl_key && key.code == KeyCode::Char('p') {
+            self.ctrl.preview = !self.ctrl.preview;
+            return Ok(Action::None);
+        }
+
         if self.ctrl.input_mode {
+            let in_subject = self.ctrl.editor.in_subject();
             match key.code {
                 KeyCode::Esc => {
                     self.ctrl.input_mode = false;
@@ -105,37 +382,16 @@ impl GitCommitState {
                 KeyCode::F(4) => {
                     return Ok(Action::Switch(Screen::GitStage(Box::new(crate::ui::git_stage_ui::GitStageState::new(ctx)?))));
                 }
-                KeyCode::Down => {
-                    self.ctrl.next()?;
-                }
-                KeyCode::Up => {
-                    self.ctrl.prev()?;
-                }
-                KeyCode::Enter => {
-                    if self.ctrl.message.trim().is_empty() {
-                        return Ok(Action::None);
+                // In the subject, Up/Down browse earlier commit messages.
+                KeyCode::Up if in_subject => self.ctrl.history_prev(),
+                KeyCode::Down if in_subject => {
+                    if !self.ctrl.history_next() {
+                        self.ctrl.editor.down();
                     }
-                let status = std::process::Command::new("git")
-                    .arg("-C")
-                    .arg(&self.ctrl.repo_root)
-                    .arg("commit")
-                    .arg("-m")
-                    .arg(&self.ctrl.message)
-                    .status()?;
-                if !status.success() {
-                    return Err(anyhow::anyhow!("git commit failed"));
                 }
-                return Ok(Action::Switch(Screen::Main(Box::new(crate::ui::main_ui::MainState::new(ctx)?))));
-            }
-                KeyCode::Backspace => {
-                    self.ctrl.message.pop();
-                }
-                KeyCode::Char(c) => {
-                    if !c.is_control() {
-                        self.ctrl.message.push(c);
-                    }
+                _ => {
+                    self.ctrl.editor.edit_key(key);
                 }
-                _ => {}
             }
             return Ok(Action::None);
         }
@@ -152,6 +408,10 @@ impl GitCommitState {
             }
             KeyCode::Char('j') | KeyCode::Down => self.ctrl.next()?,
             KeyCode::Char('k') | KeyCode::Up => self.ctrl.prev()?,
+            KeyCode::Char('S') => {
+                self.ctrl.side_by_side = toggle_side_by_side(ctx)?;
+                self.ctrl.content_scroll = 0;
+            }
             KeyCode::Char('A') | KeyCode::Char('a') => {
                 let name = self.ctrl.focus_file_name().unwrap_or_default();
                 if !name.is_empty() {
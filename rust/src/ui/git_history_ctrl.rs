@@ -1,24 +1,406 @@
 use std::cmp::min;
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
 
 use crate::app::AppContext;
-use crate::git::{self, CommitSummary};
+use crate::git::{self, CommitSummary, HistoryWalk, RefLabel};
+use crate::ui::highlight::next_generation;
+use crate::util::{now_secs, parse_date_bound};
+
+const HISTORY_PAGE: usize = 200;
+// Load the next page once the selection gets this close to the end of the list.
+const LOAD_AHEAD: usize = 20;
+// Commits one keystroke may read looking for matches; Enter or moving down reads on.
+const SEARCH_BUDGET: usize = 2000;
+
+// Parsed filter: `author:kim since:2w until:2026-01-01 hash:abc12 msg:word -merges` plus free text.
+// Free text matches author or subject as before; `msg:` searches the whole message.
+#[derive(Debug, Default, PartialEq)]
+pub struct HistoryQuery {
+    pub text: String,
+    pub author: Option<String>,
+    pub since: Option<i64>,
+    pub until: Option<i64>,
+    pub hash: Option<String>,
+    pub msg: Option<String>,
+    pub no_merges: bool,
+}
+
+// Whitespace separated, with "double quotes" keeping spaces in a value.
+fn split_query(input: &str) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut cur = String::new();
+    let mut quoted = false;
+    for c in input.chars() {
+        match c {
+            '"' => quoted = !quoted,
+            c if c.is_whitespace() && !quoted => {
+                if !cur.is_empty() {
+                    out.push(std::mem::take(&mut cur));
+                }
+            }
+            c => cur.push(c),
+        }
+    }
+    if !cur.is_empty() {
+        out.push(cur);
+    }
+    out
+}
+
+impl HistoryQuery {
+    pub fn parse(input: &str, now: i64) -> anyhow::Result<Self> {
+        let mut query = Self::default();
+        let mut words = Vec::new();
+        for token in split_query(input) {
+            if token == "-merges" {
+                query.no_merges = true;
+                continue;
+            }
+            let Some((key, value)) = token.split_once(':') else {
+                words.push(token);
+                continue;
+            };
+            // A key still being typed (`author:`) filters nothing yet.
+            let value = (!value.is_empty()).then(|| value.to_lowercase());
+            let date = |v: &Option<String>| -> anyhow::Result<Option<i64>> {
+                match v {
+                    Some(v) => parse_date_bound(v, now).map(Some).ok_or_else(|| anyhow::anyhow!("Bad date: {}", v)),
+                    None => Ok(None),
+                }
+            };
+            match key {
+                "author" => query.author = value,
+                "since" => query.since = date(&value)?,
+                "until" => query.until = date(&value)?,
+                "hash" => query.hash = value,
+                "msg" => query.msg = value,
+                _ => words.push(token),
+            }
+        }
+        query.text = words.join(" ").to_lowercase();
+        Ok(query)
+    }
+
+    pub fn matches(&self, c: &CommitSummary) -> bool {
+        if self.no_merges && c.merge {
+            return false;
+        }
+        if self.since.is_some_and(|t| c.time < t) || self.until.is_some_and(|t| c.time > t) {
+            return false;
+        }
+        if let Some(hash) = &self.hash {
+            if !c.hash.starts_with(hash.as_str()) {
+                return false;
+            }
+        }
+        if let Some(author) = &self.author {
+            if !c.author.to_lowercase().contains(author) && !c.email.to_lowercase().contains(author) {
+                return false;
+            }
+        }
+        if let Some(msg) = &self.msg {
+            if !c.message.to_lowercase().contains(msg) {
+                return false;
+            }
+        }
+        self.text.is_empty() || c.author.to_lowercase().contains(&self.text) || c.subject.to_lowercase().contains(&self.text)
+    }
+}
+
+// Graph lanes carried from one row to the next (and across pages).
+// Each lane holds the hash its line is heading down to.
+#[derive(Default)]
+pub struct GraphLanes {
+    lanes: Vec<Option<String>>,
+    seen: HashSet<String>,
+}
+
+impl GraphLanes {
+    fn free_lane(&mut self, taken: &[usize]) -> usize {
+        match (0..self.lanes.len()).find(|i| self.lanes[*i].is_none() && !taken.contains(i)) {
+            Some(i) => i,
+            None => {
+                self.lanes.push(None);
+                self.lanes.len() - 1
+            }
+        }
+    }
+
+    // Box-drawing row for `c`, then moves the lanes on to its parents.
+    // Parents already drawn (clock skew puts them above) just end their line.
+    pub fn row(&mut self, c: &CommitSummary) -> String {
+        self.seen.insert(c.hash.clone());
+        let heading_here = |l: &Option<String>| l.as_deref() == Some(c.hash.as_str());
+        let col = match self.lanes.iter().position(heading_here) {
+            Some(i) => i,
+            None => self.free_lane(&[]),
+        };
+        let converging: Vec<usize> = (0..self.lanes.len()).filter(|&i| i != col && heading_here(&self.lanes[i])).collect();
+        let passing: Vec<bool> = self.lanes.iter().map(|l| l.is_some()).collect();
+
+        let mut taken = converging.clone();
+        taken.push(col);
+        self.lanes[col] = c.parents.first().filter(|p| !self.seen.contains(*p)).cloned();
+        // (lane, whether that lane was already heading to the parent)
+        let mut forks = Vec::new();
+        let merged: Vec<&String> = c.parents.iter().skip(1).filter(|p| !self.seen.contains(*p)).collect();
+        for parent in merged {
+            match self.lanes.iter().position(|l| l.as_ref() == Some(parent)) {
+                Some(i) => forks.push((i, true)),
+                None => {
+                    let i = self.free_lane(&taken);
+                    self.lanes[i] = Some(parent.clone());
+                    taken.push(i);
+                    forks.push((i, false));
+                }
+            }
+        }
+        for &i in &converging {
+            self.lanes[i] = None;
+        }
+
+        let mut cells = vec![' '; self.lanes.len().max(passing.len()) * 2];
+        for (i, pass) in passing.iter().enumerate() {
+            if *pass {
+                cells[i * 2] = '│';
+            }
+        }
+        cells[col * 2] = '●';
+        let targets = converging.iter().map(|&i| (i, '╯', '╰')).chain(forks.iter().map(|&(i, existing)| {
+            if existing { (i, '┤', '├') } else { (i, '╮', '╭') }
+        }));
+        for (lane, right, left) in targets {
+            let (from, to) = (col.min(lane) * 2 + 1, col.max(lane) * 2);
+            for cell in &mut cells[from..to] {
+                *cell = match *cell {
+                    '│' => '┼',
+                    ' ' => '─',
+                    other => other,
+                };
+            }
+            cells[lane * 2] = if lane > col { right } else { left };
+        }
+        while self.lanes.last() == Some(&None) {
+            self.lanes.pop();
+        }
+        cells.into_iter().collect::<String>().trim_end().to_string()
+    }
+}
+
+// Popup for choosing what to browse: a listed ref, or whatever is typed (`v1.2..v1.3`, a hash).
+pub struct RefPicker {
+    pub refs: Vec<RefLabel>,
+    pub input: String,
+    pub selected: usize,
+}
+
+impl RefPicker {
+    pub fn matches(&self) -> Vec<&RefLabel> {
+        let input = self.input.trim().to_lowercase();
+        self.refs.iter().filter(|r| r.name.to_lowercase().contains(&input)).collect()
+    }
+
+    pub fn choice(&self) -> Option<String> {
+        let input = self.input.trim();
+        if input.contains("..") {
+            return Some(input.to_string());
+        }
+        match self.matches().get(self.selected) {
+            Some(r) => Some(r.name.clone()),
+            None if !input.is_empty() => Some(input.to_string()),
+            None => None,
+        }
+    }
+
+    // Tab: copies the focused ref into the input, e.g. to go on typing a range.
+    pub fn complete(&mut self) {
+        if let Some(name) = self.matches().get(self.selected).map(|r| r.name.clone()) {
+            self.input = name;
+            self.selected = 0;
+        }
+    }
+}
+
+// What can be done with the focused commit from the actions menu (`a`).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CommitAction {
+    CherryPick,
+    Revert,
+    ResetSoft,
+    ResetMixed,
+    ResetHard,
+    Branch,
+    Tag,
+    Checkout,
+}
+
+impl CommitAction {
+    pub const ALL: [CommitAction; 8] = [
+        CommitAction::CherryPick,
+        CommitAction::Revert,
+        CommitAction::ResetSoft,
+        CommitAction::ResetMixed,
+        CommitAction::ResetHard,
+        CommitAction::Branch,
+        CommitAction::Tag,
+        CommitAction::Checkout,
+    ];
+
+    pub fn key(self) -> char {
+        match self {
+            CommitAction::CherryPick => 'p',
+            CommitAction::Revert => 'v',
+            CommitAction::ResetSoft => 's',
+            CommitAction::ResetMixed => 'm',
+            CommitAction::ResetHard => 'h',
+            CommitAction::Branch => 'b',
+            CommitAction::Tag => 't',
+            CommitAction::Checkout => 'o',
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            CommitAction::CherryPick => "cherry-pick onto the current branch",
+            CommitAction::Revert => "revert",
+            CommitAction::ResetSoft => "reset --soft (keep index and files)",
+            CommitAction::ResetMixed => "reset --mixed (keep files)",
+            CommitAction::ResetHard => "reset --hard (drop all changes)",
+            CommitAction::Branch => "create branch here",
+            CommitAction::Tag => "create tag here",
+            CommitAction::Checkout => "checkout (detached HEAD)",
+        }
+    }
+
+    pub fn needs_name(self) -> bool {
+        matches!(self, CommitAction::Branch | CommitAction::Tag)
+    }
+
+    // Loses work without a way back, so it is confirmed first.
+    pub fn needs_confirm(self) -> bool {
+        self == CommitAction::ResetHard
+    }
+}
 
 pub struct GitHistoryCtrl {
     pub repo_root: PathBuf,
     pub commits: Vec<CommitSummary>,
     pub selected_idx: usize,
     pub filter: String,
+    pub query: HistoryQuery,
+    pub filter_error: Option<String>,
     pub filtered: Vec<CommitSummary>,
     pub detail: Vec<String>,
+    // Generation of `detail`, for the syntax cache.
+    pub detail_gen: u64,
     pub detail_scroll: u16,
+    pub side_by_side: bool,
+    pub relative_dates: bool,
+    // What the list walks: "HEAD", a ref or a range.
+    pub rev: String,
+    pub picker: Option<RefPicker>,
+    // Where to continue reading history; None once everything is loaded (or for fixed data).
+    pub walk: Option<HistoryWalk>,
+    pub page_size: usize,
+    pub search_budget: usize,
+    // The last read stopped at `search_budget` before finding enough matches.
+    pub searching: bool,
+    pub total: Option<usize>,
+    // Graph row per entry of `commits`; only drawn while the filter is empty.
+    pub graph: Vec<String>,
+    pub lanes: GraphLanes,
+    pub refs: HashMap<String, Vec<RefLabel>>,
+    // Commit marked with `m`; while set the detail compares it with the focused commit.
+    pub mark: Option<CommitSummary>,
+    // (path, detail line of its "diff --git") for the file list pane.
+    pub files: Vec<(String, usize)>,
+    pub file_idx: usize,
 }
 
 impl GitHistoryCtrl {
-    pub fn new(_ctx: &AppContext) -> anyhow::Result<Self> {
-        let repo_root = git::repo_root()?;
-        let commits = git::commit_history_at(&repo_root, 200)?;
-        Self::with_data(repo_root, commits)
+    pub fn new(ctx: &AppContext, rev: &str) -> anyhow::Result<Self> {
+        let mut ctrl = Self::with_walk(git::repo_root()?, HISTORY_PAGE, rev)?;
+        ctrl.side_by_side = ctx.config.diff_side_by_side;
+        ctrl.relative_dates = ctx.config.history_relative_dates;
+        Ok(ctrl)
+    }
+
+    pub fn with_walk(repo_root: PathBuf, page_size: usize, rev: &str) -> anyhow::Result<Self> {
+        let refs = git::ref_labels_at(&repo_root).unwrap_or_default();
+        let mut ctrl = Self::with_data(repo_root, Vec::new())?;
+        ctrl.refs = refs;
+        ctrl.page_size = page_size;
+        ctrl.set_rev(rev)?;
+        Ok(ctrl)
+    }
+
+    // Restarts the list from `rev`, a ref or a range such as `origin/main..HEAD`.
+    pub fn set_rev(&mut self, rev: &str) -> anyhow::Result<()> {
+        let mut walk = HistoryWalk::rev_at(&self.repo_root, rev)?;
+        self.commits = walk.next_page(self.page_size)?;
+        self.walk = if walk.is_done() { None } else { Some(walk) };
+        self.total = git::commit_count_at(&self.repo_root, rev).ok();
+        self.rev = rev.to_string();
+        self.graph.clear();
+        self.lanes = GraphLanes::default();
+        self.add_graph_rows(0);
+        self.selected_idx = 0;
+        self.apply_filter()
+    }
+
+    // Runs `action` on the focused commit, then reloads the list and refs, keeping the focus.
+    // A cherry-pick or revert that stops on conflicts returns the error with the operation left
+    // in progress, for GitStage to take over.
+    pub fn run_action(&mut self, action: CommitAction, name: &str) -> anyhow::Result<String> {
+        let Some(commit) = self.focus_commit().cloned() else {
+            return Ok(String::new());
+        };
+        let hash = commit.hash.as_str();
+        let mainline: &[&str] = if commit.merge { &["-m", "1"] } else { &[] };
+        let args: Vec<&str> = match action {
+            CommitAction::CherryPick => [&["cherry-pick"], mainline, &[hash]].concat(),
+            CommitAction::Revert => [&["revert", "--no-edit"], mainline, &[hash]].concat(),
+            CommitAction::ResetSoft => vec!["reset", "-q", "--soft", hash],
+            CommitAction::ResetMixed => vec!["reset", "-q", "--mixed", hash],
+            CommitAction::ResetHard => vec!["reset", "-q", "--hard", hash],
+            CommitAction::Branch => vec!["branch", name, hash],
+            CommitAction::Tag => vec!["tag", name, hash],
+            CommitAction::Checkout => vec!["checkout", "-q", "--detach", hash],
+        };
+        git::run_git_at("History", &self.repo_root, &args)?;
+        self.refs = git::ref_labels_at(&self.repo_root).unwrap_or_default();
+        self.set_rev(&self.rev.clone())?;
+        if let Some(idx) = self.filtered.iter().position(|c| c.hash == commit.hash) {
+            self.set_selected(idx)?;
+        }
+        let hash = commit.short_hash();
+        Ok(match action {
+            CommitAction::CherryPick => format!("Cherry-picked {}", hash),
+            CommitAction::Revert => format!("Reverted {}", hash),
+            CommitAction::ResetSoft | CommitAction::ResetMixed | CommitAction::ResetHard => format!("Reset to {}", hash),
+            CommitAction::Branch => format!("Created branch {} at {}", name, hash),
+            CommitAction::Tag => format!("Created tag {} at {}", name, hash),
+            CommitAction::Checkout => format!("HEAD is now at {}", hash),
+        })
+    }
+
+    pub fn open_ref_picker(&mut self) -> anyhow::Result<()> {
+        self.picker = Some(RefPicker {
+            refs: git::ref_list_at(&self.repo_root)?,
+            input: String::new(),
+            selected: 0,
+        });
+        Ok(())
+    }
+
+    // Switches to the picker's choice; the picker stays open when it has none.
+    pub fn pick_ref(&mut self) -> anyhow::Result<()> {
+        let Some(rev) = self.picker.as_ref().and_then(|p| p.choice()) else {
+            return Ok(());
+        };
+        self.picker = None;
+        self.set_rev(&rev)
     }
 
     pub fn with_data(repo_root: PathBuf, commits: Vec<CommitSummary>) -> anyhow::Result<Self> {
@@ -27,31 +409,133 @@ impl GitHistoryCtrl {
             commits,
             selected_idx: 0,
             filter: String::new(),
+            query: HistoryQuery::default(),
+            filter_error: None,
             filtered: Vec::new(),
             detail: vec!["< Nothing to display >".to_string()],
+            detail_gen: 0,
             detail_scroll: 0,
+            side_by_side: false,
+            relative_dates: false,
+            rev: "HEAD".to_string(),
+            picker: None,
+            walk: None,
+            page_size: HISTORY_PAGE,
+            search_budget: SEARCH_BUDGET,
+            searching: false,
+            total: None,
+            graph: Vec::new(),
+            lanes: GraphLanes::default(),
+            refs: HashMap::new(),
+            mark: None,
+            files: Vec::new(),
+            file_idx: 0,
         };
+        ctrl.add_graph_rows(0);
         ctrl.apply_filter()?;
         Ok(ctrl)
     }
 
-    pub fn apply_filter(&mut self) -> anyhow::Result<()> {
+    fn add_graph_rows(&mut self, from: usize) {
+        for c in &self.commits[from..] {
+            let row = self.lanes.row(c);
+            self.graph.push(row);
+        }
+    }
+
+    // Graph row for the `idx`-th listed commit; none while filtering, as rows would not connect.
+    pub fn graph_row(&self, idx: usize) -> Option<&str> {
+        if self.query != HistoryQuery::default() {
+            return None;
+        }
+        self.graph.get(idx).map(|g| g.as_str())
+    }
+
+    pub fn ref_labels(&self, hash: &str) -> &[RefLabel] {
+        self.refs.get(hash).map(|v| v.as_slice()).unwrap_or(&[])
+    }
+
+    pub fn is_fully_loaded(&self) -> bool {
+        self.walk.is_none()
+    }
+
+    // Reads more history until the selection is `LOAD_AHEAD` away from the end of the
+    // filtered list, so a narrow filter keeps walking until it has enough matches, up to
+    // `search_budget` commits per call.
+    fn load_more(&mut self) -> anyhow::Result<()> {
+        let mut read = 0;
+        self.searching = false;
+        while self.selected_idx + LOAD_AHEAD >= self.filtered.len() {
+            let Some(walk) = self.walk.as_mut() else {
+                break;
+            };
+            // The walk is newest first, so nothing further down can match `since:`.
+            if let (Some(since), Some(next)) = (self.query.since, walk.peek_time()) {
+                if next < since {
+                    break;
+                }
+            }
+            if read >= self.search_budget {
+                self.searching = true;
+                break;
+            }
+            let page = walk.next_page(self.page_size)?;
+            read += page.len();
+            if walk.is_done() {
+                self.walk = None;
+            }
+            let matched: Vec<CommitSummary> = page.iter().filter(|c| self.query.matches(c)).cloned().collect();
+            self.filtered.extend(matched);
+            let from = self.commits.len();
+            self.commits.extend(page);
+            self.add_graph_rows(from);
+        }
+        Ok(())
+    }
+
+    // "Commits" section title: what is shown out of what has been read.
+    pub fn count_label(&self) -> String {
+        if let Some(err) = &self.filter_error {
+            return err.clone();
+        }
+        let loaded = self.commits.len();
+        let total = match (self.total, self.is_fully_loaded()) {
+            (_, true) => loaded.to_string(),
+            (Some(total), false) => total.to_string(),
+            (None, false) => "?".to_string(),
+        };
         if self.filter.trim().is_empty() {
-            self.filtered = self.commits.clone();
+            format!("{}/{}", loaded, total)
+        } else if self.searching {
+            format!("searching… {} matches, {}/{} loaded", self.filtered.len(), loaded, total)
         } else {
-            let f = self.filter.to_lowercase();
-            self.filtered = self
-                .commits
-                .iter()
-                .filter(|c| {
-                    c.author.to_lowercase().contains(&f) || c.subject.to_lowercase().contains(&f)
-                })
-                .cloned()
-                .collect();
+            format!("{} matches, {}/{} loaded", self.filtered.len(), loaded, total)
+        }
+    }
+
+    pub fn apply_filter(&mut self) -> anyhow::Result<()> {
+        match HistoryQuery::parse(&self.filter, now_secs()) {
+            Ok(query) => {
+                self.query = query;
+                self.filter_error = None;
+            }
+            Err(err) => {
+                // Keep the walk where it is until the query makes sense again.
+                self.filter_error = Some(err.to_string());
+                self.searching = false;
+                self.filtered.clear();
+                self.selected_idx = 0;
+                self.detail = vec![format!("< {} >", err)];
+                self.detail_gen = next_generation();
+                return Ok(());
+            }
         }
+        self.filtered = self.commits.iter().filter(|c| self.query.matches(c)).cloned().collect();
+        self.load_more()?;
         if self.filtered.is_empty() {
             self.selected_idx = 0;
             self.detail = vec!["< No commit >".to_string()];
+            self.detail_gen = next_generation();
             self.detail_scroll = 0;
             return Ok(());
         }
@@ -75,6 +559,7 @@ impl GitHistoryCtrl {
             return Ok(());
         }
         self.selected_idx = min(self.selected_idx + 1, self.filtered.len().saturating_sub(1));
+        self.load_more()?;
         self.detail_scroll = 0;
         self.load_detail()?;
         Ok(())
@@ -98,6 +583,7 @@ impl GitHistoryCtrl {
             self.selected_idx + 10,
             self.filtered.len().saturating_sub(1),
         );
+        self.load_more()?;
         self.detail_scroll = 0;
         self.load_detail()?;
         Ok(())
@@ -116,18 +602,82 @@ impl GitHistoryCtrl {
     pub fn set_selected(&mut self, idx: usize) -> anyhow::Result<()> {
         if idx < self.filtered.len() {
             self.selected_idx = idx;
+            self.load_more()?;
             self.detail_scroll = 0;
             self.load_detail()?;
         }
         Ok(())
     }
 
+    // Marks the focused commit to compare others with; marking it again clears the mark.
+    pub fn toggle_mark(&mut self) -> anyhow::Result<()> {
+        let Some(commit) = self.focus_commit() else {
+            return Ok(());
+        };
+        self.mark = match &self.mark {
+            Some(mark) if mark.hash == commit.hash => None,
+            _ => Some(commit.clone()),
+        };
+        self.detail_scroll = 0;
+        self.load_detail()
+    }
+
+    pub fn clear_mark(&mut self) -> anyhow::Result<()> {
+        self.mark = None;
+        self.detail_scroll = 0;
+        self.load_detail()
+    }
+
+    // (older, newer) while the detail compares the mark with another commit.
+    pub fn compare_range(&self) -> Option<(&CommitSummary, &CommitSummary)> {
+        let mark = self.mark.as_ref()?;
+        let commit = self.focus_commit()?;
+        if mark.hash == commit.hash {
+            return None;
+        }
+        // Same second: the list is newest first, so the one further down is older.
+        let mark_older = match mark.time.cmp(&commit.time) {
+            std::cmp::Ordering::Equal => self.filtered.iter().position(|c| c.hash == mark.hash).is_none_or(|p| p > self.selected_idx),
+            ord => ord.is_lt(),
+        };
+        let (from, to) = if mark_older { (mark, commit) } else { (commit, mark) };
+        Some((from, to))
+    }
+
+    // Moves the file list selection by `delta` and returns the detail line to scroll to.
+    pub fn step_file(&mut self, delta: isize) -> Option<usize> {
+        if self.files.is_empty() {
+            return None;
+        }
+        let last = self.files.len() as isize - 1;
+        self.file_idx = (self.file_idx as isize + delta).clamp(0, last) as usize;
+        Some(self.files[self.file_idx].1)
+    }
+
+    pub fn select_file(&mut self, idx: usize) -> Option<usize> {
+        let line = self.files.get(idx)?.1;
+        self.file_idx = idx;
+        Some(line)
+    }
+
     pub fn load_detail(&mut self) -> anyhow::Result<()> {
+        self.load_commit_detail();
+        self.detail_gen = next_generation();
+        self.files = diff_files(&self.detail);
+        self.file_idx = 0;
+        Ok(())
+    }
+
+    fn load_commit_detail(&mut self) {
         let Some(commit) = self.focus_commit() else {
             self.detail = vec!["< No commit >".to_string()];
-            return Ok(());
+            return;
         };
-        match git::commit_detail_at(&self.repo_root, &commit.hash) {
+        let loaded = match self.compare_range() {
+            Some((from, to)) => git::compare_commits_at(&self.repo_root, &from.hash, &to.hash),
+            None => git::commit_detail_at(&self.repo_root, &commit.hash),
+        };
+        match loaded {
             Ok(lines) if !lines.is_empty() => {
                 self.detail = lines;
             }
@@ -138,33 +688,59 @@ impl GitHistoryCtrl {
                 self.detail = vec![format!("Error loading detail: {}", err)];
             }
         }
-        Ok(())
     }
 }
 
+// Files of a patch with the index of their "diff --git" line.
+pub fn diff_files(detail: &[String]) -> Vec<(String, usize)> {
+    detail
+        .iter()
+        .enumerate()
+        .filter_map(|(i, line)| {
+            let rest = line.strip_prefix("diff --git ")?;
+            let pos = rest.rfind(" b/")?;
+            Some((rest[pos + 3..].to_string(), i))
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::system::system;
+    use crate::ui::common::TestEnv;
 
     fn sample_commits() -> Vec<CommitSummary> {
         vec![
             CommitSummary {
                 hash: "aaa1111".to_string(),
                 author: "Alice".to_string(),
+                email: "alice@example.com".to_string(),
                 date: "2026-02-15".to_string(),
+                time: 0,
+                offset: 0,
                 subject: "Add history screen".to_string(),
+                ..Default::default()
             },
             CommitSummary {
                 hash: "bbb2222".to_string(),
                 author: "Bob".to_string(),
+                email: "bob@example.com".to_string(),
                 date: "2026-02-14".to_string(),
+                time: 0,
+                offset: 0,
                 subject: "Fix filter logic".to_string(),
+                ..Default::default()
             },
             CommitSummary {
                 hash: "ccc3333".to_string(),
                 author: "Chris".to_string(),
+                email: "chris@example.com".to_string(),
                 date: "2026-02-13".to_string(),
+                time: 0,
+                offset: 0,
                 subject: "Refactor stage screen".to_string(),
+                ..Default::default()
             },
         ]
     }
@@ -183,6 +759,229 @@ mod tests {
         assert_eq!(ctrl.filtered[0].subject, "Fix filter logic");
     }
 
+    #[test]
+    fn test_git_history_ctrl_loads_pages_on_demand() {
+        let env = TestEnv::repo("test_git_history_ctrl_pages");
+        for i in 0..30 {
+            std::fs::write(env.root.join("a.txt"), i.to_string()).unwrap();
+            let msg = if i == 0 { "first".to_string() } else { format!("c{}", i) };
+            let _ = system(&format!("git add . && git commit -q -m {}", msg));
+        }
+
+        let mut ctrl = GitHistoryCtrl::with_walk(git::repo_root().unwrap(), 5, "HEAD").unwrap();
+        // The first page is topped up to keep LOAD_AHEAD commits below the selection.
+        assert_eq!(ctrl.commits.len(), 25);
+        assert_eq!(ctrl.count_label(), "25/30");
+        ctrl.set_selected(10).unwrap();
+        assert!(ctrl.is_fully_loaded());
+        assert_eq!(ctrl.count_label(), "30/30");
+        assert_eq!(ctrl.filtered[29].subject, "first");
+
+        let mut ctrl = GitHistoryCtrl::with_walk(git::repo_root().unwrap(), 5, "HEAD").unwrap();
+        ctrl.set_filter("first".to_string()).unwrap();
+        assert_eq!(ctrl.filtered.len(), 1);
+        assert_eq!(ctrl.count_label(), "1 matches, 30/30 loaded");
+
+        // A sparse filter stops at the budget and goes on when asked again.
+        let mut ctrl = GitHistoryCtrl::with_walk(git::repo_root().unwrap(), 1, "HEAD").unwrap();
+        assert_eq!(ctrl.commits.len(), 21);
+        ctrl.search_budget = 5;
+        ctrl.set_filter("first".to_string()).unwrap();
+        assert!(ctrl.filtered.is_empty());
+        assert_eq!(ctrl.count_label(), "searching… 0 matches, 26/30 loaded");
+        ctrl.apply_filter().unwrap();
+        assert_eq!(ctrl.filtered.len(), 1);
+        assert!(!ctrl.searching);
+    }
+
+    #[test]
+    fn test_history_query_tokens() {
+        let now = 100 * 86_400;
+        let query = HistoryQuery::parse("author:Kim since:2w until:1970-03-01 hash:ABC12 msg:\"two words\" -merges fix it", now).unwrap();
+        assert_eq!(query.author.as_deref(), Some("kim"));
+        assert_eq!(query.since, Some(86 * 86_400));
+        assert_eq!(query.until, Some(59 * 86_400));
+        assert_eq!(query.hash.as_deref(), Some("abc12"));
+        assert_eq!(query.msg.as_deref(), Some("two words"));
+        assert!(query.no_merges);
+        assert_eq!(query.text, "fix it");
+        assert_eq!(HistoryQuery::parse("author: see:this", now).unwrap().text, "see:this");
+        assert!(HistoryQuery::parse("since:yesterday", now).is_err());
+
+        let mut ctrl = GitHistoryCtrl::with_data(PathBuf::from("."), sample_commits()).unwrap();
+        ctrl.commits[1].message = "Fix filter logic\n\nThe body mentions Kim.".to_string();
+        ctrl.commits[2].merge = true;
+        ctrl.set_filter("msg:kim".to_string()).unwrap();
+        assert_eq!(ctrl.filtered.len(), 1);
+        ctrl.set_filter("-merges".to_string()).unwrap();
+        assert_eq!(ctrl.filtered.len(), 2);
+        ctrl.set_filter("hash:bbb22 author:bob@".to_string()).unwrap();
+        assert_eq!(ctrl.filtered[0].subject, "Fix filter logic");
+        ctrl.set_filter("until:2026-13-01".to_string()).unwrap();
+        assert!(ctrl.filtered.is_empty());
+        assert_eq!(ctrl.count_label(), "Bad date: 2026-13-01");
+    }
+
+    fn graph_commit(hash: &str, parents: &[&str]) -> CommitSummary {
+        CommitSummary {
+            hash: hash.to_string(),
+            parents: parents.iter().map(|p| p.to_string()).collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_graph_lanes_fork_and_merge() {
+        // m merges b into a; both branch off base.
+        let commits = [
+            graph_commit("m", &["a", "b"]),
+            graph_commit("a", &["base"]),
+            graph_commit("b", &["base"]),
+            graph_commit("base", &[]),
+        ];
+        let mut lanes = GraphLanes::default();
+        let rows: Vec<String> = commits.iter().map(|c| lanes.row(c)).collect();
+        assert_eq!(rows, ["●─╮", "● │", "│ ●", "●─╯"]);
+        assert!(lanes.lanes.is_empty());
+    }
+
+    #[test]
+    fn test_git_history_ctrl_graph_and_refs() {
+        let env = TestEnv::repo("test_git_history_ctrl_graph");
+        std::fs::write(env.root.join("a.txt"), "a").unwrap();
+        let _ = system("git add . && git commit -q -m base && git tag v1 && git checkout -q -b topic");
+        std::fs::write(env.root.join("b.txt"), "b").unwrap();
+        let _ = system("git add . && git commit -q -m topic && git checkout -q main");
+        std::fs::write(env.root.join("c.txt"), "c").unwrap();
+        let _ = system("git add . && git commit -q -m side && git merge -q --no-ff --no-edit topic");
+
+        let ctrl = GitHistoryCtrl::with_walk(git::repo_root().unwrap(), 50, "HEAD").unwrap();
+        assert_eq!(ctrl.graph.len(), 4);
+        assert_eq!(ctrl.graph_row(0), Some("●─╮"));
+        assert_eq!(ctrl.graph_row(3), Some("●─╯"));
+        let head = ctrl.ref_labels(&ctrl.commits[0].hash);
+        assert_eq!(head[0].name, "HEAD -> main");
+        let base = ctrl.ref_labels(&ctrl.commits[3].hash);
+        assert_eq!(base[0].kind, git::RefKind::Tag);
+        assert_eq!(base[0].name, "v1");
+    }
+
+    #[test]
+    fn test_git_history_ctrl_browse_ref_and_range() {
+        let env = TestEnv::repo("test_git_history_ctrl_rev");
+        std::fs::write(env.root.join("a.txt"), "a").unwrap();
+        let _ = system("git add . && git commit -q -m base && git tag -a v1 -m v1 && git checkout -q -b topic");
+        for name in ["t1", "t2"] {
+            std::fs::write(env.root.join(name), name).unwrap();
+            let _ = system(&format!("git add . && git commit -q -m {}", name));
+        }
+        let _ = system("git checkout -q main");
+
+        let mut ctrl = GitHistoryCtrl::with_walk(git::repo_root().unwrap(), 50, "HEAD").unwrap();
+        assert_eq!(ctrl.commits.len(), 1);
+        ctrl.open_ref_picker().unwrap();
+        let picker = ctrl.picker.as_mut().unwrap();
+        let names: Vec<&str> = picker.refs.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(names, ["HEAD", "main", "topic", "v1"]);
+        picker.input = "top".to_string();
+        ctrl.pick_ref().unwrap();
+        assert!(ctrl.picker.is_none());
+        assert_eq!(ctrl.rev, "topic");
+        assert_eq!(ctrl.commits.len(), 3);
+        assert_eq!(ctrl.focus_commit().unwrap().subject, "t2");
+
+        ctrl.set_rev("v1..topic").unwrap();
+        let subjects: Vec<&str> = ctrl.commits.iter().map(|c| c.subject.as_str()).collect();
+        assert_eq!(subjects, ["t2", "t1"]);
+        assert!(ctrl.is_fully_loaded());
+        assert_eq!(ctrl.count_label(), "2/2");
+        assert!(ctrl.set_rev("nope..topic").is_err());
+
+        // A merge of the hidden side keeps only the listed parent, so no lane is left open.
+        std::fs::write(env.root.join("m1"), "m1").unwrap();
+        let _ = system("git add . && git commit -q -m m1 && git checkout -q topic && git merge -q --no-ff main -m merge");
+        ctrl.set_rev("main..topic").unwrap();
+        let subjects: Vec<&str> = ctrl.commits.iter().map(|c| c.subject.as_str()).collect();
+        assert_eq!(subjects, ["merge", "t2", "t1"]);
+        assert!(ctrl.commits[0].merge);
+        assert_eq!(ctrl.commits[0].parents.len(), 1);
+        assert_eq!(ctrl.graph, ["●", "●", "●"]);
+        ctrl.set_filter("-merges".to_string()).unwrap();
+        assert_eq!(ctrl.filtered.len(), 2);
+    }
+
+    #[test]
+    fn test_git_history_ctrl_commit_actions() {
+        let env = TestEnv::repo("test_git_history_ctrl_actions");
+        std::fs::write(env.root.join("a.txt"), "a").unwrap();
+        let _ = system("git add . && git commit -q -m base && git checkout -q -b topic");
+        std::fs::write(env.root.join("b.txt"), "b").unwrap();
+        let _ = system("git add . && git commit -q -m pick-me && git checkout -q main");
+        let subjects = || system("git log --format=%s").unwrap();
+
+        let mut ctrl = GitHistoryCtrl::with_walk(git::repo_root().unwrap(), 50, "HEAD").unwrap();
+        ctrl.set_rev("topic").unwrap();
+        assert_eq!(ctrl.focus_commit().unwrap().subject, "pick-me");
+        ctrl.run_action(CommitAction::Branch, "keep").unwrap();
+        ctrl.run_action(CommitAction::Tag, "v1").unwrap();
+        assert_eq!(ctrl.ref_labels(&ctrl.focus_commit().unwrap().hash).len(), 3);
+        ctrl.run_action(CommitAction::CherryPick, "").unwrap();
+        assert_eq!(subjects(), "pick-me\nbase");
+
+        ctrl.set_rev("HEAD").unwrap();
+        ctrl.run_action(CommitAction::Revert, "").unwrap();
+        assert!(subjects().starts_with("Revert \"pick-me\""));
+        assert!(!env.root.join("b.txt").exists());
+
+        ctrl.set_rev("HEAD").unwrap();
+        ctrl.set_selected(1).unwrap();
+        ctrl.run_action(CommitAction::ResetSoft, "").unwrap();
+        assert_eq!(subjects(), "pick-me\nbase");
+        assert_eq!(system("git status --porcelain").unwrap(), "D  b.txt");
+        ctrl.set_selected(1).unwrap();
+        ctrl.run_action(CommitAction::ResetHard, "").unwrap();
+        assert_eq!(system("git status --porcelain").unwrap(), "");
+        assert_eq!(ctrl.focus_commit().unwrap().subject, "base");
+
+        ctrl.run_action(CommitAction::Checkout, "").unwrap();
+        assert_eq!(system("git rev-parse --abbrev-ref HEAD").unwrap(), "HEAD");
+        assert!(ctrl.set_rev("topic").is_ok());
+        assert!(ctrl.run_action(CommitAction::Branch, "keep").is_err());
+    }
+
+    #[test]
+    fn test_git_history_ctrl_compare_marked_commit() {
+        let env = TestEnv::repo("test_git_history_ctrl_compare");
+        std::fs::write(env.root.join("a.txt"), "a\n").unwrap();
+        let _ = system("git add . && git commit -q -m first");
+        std::fs::write(env.root.join("b.txt"), "b\n").unwrap();
+        let _ = system("git add . && git commit -q -m second");
+        std::fs::write(env.root.join("a.txt"), "A\n").unwrap();
+        let _ = system("git commit -q -am third");
+
+        let mut ctrl = GitHistoryCtrl::with_walk(git::repo_root().unwrap(), 50, "HEAD").unwrap();
+        assert_eq!(ctrl.files.iter().map(|f| f.0.as_str()).collect::<Vec<_>>(), ["a.txt"]);
+        ctrl.toggle_mark().unwrap();
+        assert!(ctrl.compare_range().is_none());
+        ctrl.set_selected(2).unwrap();
+        let (from, to) = ctrl.compare_range().unwrap();
+        assert_eq!((from.hash.as_str(), to.hash.as_str()), (ctrl.filtered[2].hash.as_str(), ctrl.filtered[0].hash.as_str()));
+        assert!(ctrl.detail[0].starts_with("compare "));
+        assert!(ctrl.detail.contains(&"Files changed: 2".to_string()));
+        assert!(ctrl.detail.contains(&"+A\n".to_string()));
+        assert!(ctrl.detail.contains(&"+b\n".to_string()));
+        assert_eq!(ctrl.files.iter().map(|f| f.0.as_str()).collect::<Vec<_>>(), ["a.txt", "b.txt"]);
+
+        let line = ctrl.step_file(1).unwrap();
+        assert_eq!(ctrl.detail[line], "diff --git a/b.txt b/b.txt");
+        assert_eq!(ctrl.step_file(1), Some(line));
+        assert_eq!(ctrl.select_file(0), Some(ctrl.files[0].1));
+
+        ctrl.clear_mark().unwrap();
+        assert!(ctrl.detail[0].starts_with("commit "));
+        assert_eq!(ctrl.files.len(), 1);
+    }
+
     #[test]
     fn test_git_history_ctrl_navigation_bounds() {
         let repo = PathBuf::from(".");
@@ -3,12 +3,19 @@ use std::time::Instant;
 use crossterm::event::{KeyCode, KeyEvent, MouseEvent, MouseEventKind};
 use ratatui::layout::{Constraint, Direction, Layout, Rect};
 use ratatui::style::{Color, Modifier, Style};
-use ratatui::text::{Line, Text};
-use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph};
+use ratatui::text::{Line, Span, Text};
+use ratatui::widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph};
 
 use crate::app::AppContext;
-use crate::ui::common::{format_diff_lines, is_double_click, mouse_pos, Action, Screen};
-use crate::ui::git_history_ctrl::GitHistoryCtrl;
+use crate::git::{CommitSummary, RefKind, RefLabel};
+use crate::ui::blame_ui::BlameState;
+use crate::ui::common::{
+    centered_rect, format_diff_lines, format_diff_side_by_side, is_double_click, mouse_pos, toggle_side_by_side,
+    Action, Screen,
+};
+use crate::ui::git_history_ctrl::{CommitAction, GitHistoryCtrl};
+use crate::ui::highlight::SyntaxCache;
+use crate::util::now_secs;
 
 const SECTION_TITLE_BG: Color = Color::DarkGray;
 const SECTION_TITLE_FG: Color = Color::White;
@@ -21,18 +28,73 @@ fn section_title_line(label: &str) -> Line<'static> {
     )
 }
 
+fn ref_style(kind: RefKind) -> Style {
+    match kind {
+        RefKind::Head => Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD),
+        RefKind::Local => Style::default().fg(Color::Green),
+        RefKind::Remote => Style::default().fg(Color::Red),
+        RefKind::Tag => Style::default().fg(Color::Yellow),
+    }
+}
+
+// `git log --graph --oneline` order: graph, hash, date, author, (refs) subject.
+fn commit_line(c: &CommitSummary, graph: Option<&str>, refs: &[RefLabel], relative: bool, now: i64) -> Line<'static> {
+    let mut spans = Vec::new();
+    if let Some(graph) = graph {
+        spans.push(Span::styled(format!("{} ", graph), Style::default().fg(Color::Magenta)));
+    }
+    spans.push(Span::styled(format!("{} ", c.short_hash()), Style::default().fg(Color::Yellow)));
+    spans.push(Span::raw(format!("{} {} ", c.date_label(relative, now), c.author)));
+    if !refs.is_empty() {
+        spans.push(Span::raw("("));
+        for (i, r) in refs.iter().enumerate() {
+            if i > 0 {
+                spans.push(Span::raw(", "));
+            }
+            let name = if r.kind == RefKind::Tag { format!("tag: {}", r.name) } else { r.name.clone() };
+            spans.push(Span::styled(name, ref_style(r.kind)));
+        }
+        spans.push(Span::raw(") "));
+    }
+    spans.push(Span::raw(c.subject.clone()));
+    Line::from(spans)
+}
+
+// Popups of the commit actions menu (`a`).
+pub enum ActionPopup {
+    Menu,
+    Name(CommitAction, String),
+    Confirm(CommitAction),
+}
+
+// Where `q` goes back to; Blame comes back as it was left, parent re-blames included.
+pub enum HistoryOrigin {
+    Main,
+    Blame(Box<BlameState>),
+}
+
 pub struct GitHistoryState {
     pub ctrl: GitHistoryCtrl,
     pub list_state: ListState,
     pub input_mode: bool,
     pub list_area: Option<Rect>,
     pub detail_area: Option<Rect>,
+    pub files_area: Option<Rect>,
+    pub files_state: ListState,
     pub last_click: Option<(Instant, usize)>,
+    pub syntax: SyntaxCache,
+    pub action_popup: Option<ActionPopup>,
+    pub origin: HistoryOrigin,
 }
 
 impl GitHistoryState {
     pub fn new(ctx: &AppContext) -> anyhow::Result<Self> {
-        let ctrl = GitHistoryCtrl::new(ctx)?;
+        Self::at_rev(ctx, "HEAD")
+    }
+
+    // Opened on `rev`, e.g. a commit picked in Blame, whose detail then shows first.
+    pub fn at_rev(ctx: &AppContext, rev: &str) -> anyhow::Result<Self> {
+        let ctrl = GitHistoryCtrl::new(ctx, rev)?;
         let mut list_state = ListState::default();
         list_state.select(Some(ctrl.selected_idx));
         Ok(Self {
@@ -41,7 +103,12 @@ impl GitHistoryState {
             input_mode: false,
             list_area: None,
             detail_area: None,
+            files_area: None,
+            files_state: ListState::default(),
             last_click: None,
+            syntax: SyntaxCache::new(),
+            action_popup: None,
+            origin: HistoryOrigin::Main,
         })
     }
 
@@ -59,9 +126,9 @@ impl GitHistoryState {
             .split(f.size());
 
         let filter_title = if self.input_mode {
-            "Filter (author, subject) [input]"
+            "Filter (text, author:, since:, until:, hash:, msg:, -merges) [input]"
         } else {
-            "Filter (author, subject)"
+            "Filter (text, author:, since:, until:, hash:, msg:, -merges)"
         };
         f.render_widget(
             Paragraph::new(section_title_line(filter_title))
@@ -79,7 +146,11 @@ impl GitHistoryState {
         }
 
         f.render_widget(
-            Paragraph::new(section_title_line("Commits"))
+            Paragraph::new(section_title_line(&if self.ctrl.rev == "HEAD" {
+                format!("Commits ({})", self.ctrl.count_label())
+            } else {
+                format!("Commits [{}] ({})", self.ctrl.rev, self.ctrl.count_label())
+            }))
                 .style(Style::default().bg(SECTION_TITLE_BG).fg(SECTION_TITLE_FG)),
             layout[2],
         );
@@ -87,10 +158,20 @@ impl GitHistoryState {
         let items: Vec<ListItem> = if self.ctrl.filtered.is_empty() {
             vec![ListItem::new("< No commit >")]
         } else {
+            let now = now_secs();
             self.ctrl
                 .filtered
                 .iter()
-                .map(|c| ListItem::new(c.to_list_label()))
+                .enumerate()
+                .map(|(i, c)| {
+                    let graph = self.ctrl.graph_row(i);
+                    let mut line = commit_line(c, graph, self.ctrl.ref_labels(&c.hash), self.ctrl.relative_dates, now);
+                    if let Some(mark) = &self.ctrl.mark {
+                        let marker = if mark.hash == c.hash { "▶ " } else { "  " };
+                        line.spans.insert(0, Span::styled(marker, Style::default().fg(Color::LightMagenta)));
+                    }
+                    ListItem::new(line)
+                })
                 .collect()
         };
         self.list_state.select(if self.ctrl.filtered.is_empty() {
@@ -104,22 +185,247 @@ impl GitHistoryState {
         f.render_stateful_widget(list, layout[3], &mut self.list_state);
         self.list_area = Some(layout[3]);
 
+        let compare = self.ctrl.compare_range();
+        let detail_title = match (&compare, &self.ctrl.mark) {
+            (Some((from, to)), _) => format!("Compare {}..{} (n/N: next/prev file, Esc: clear mark)", from.short_hash(), to.short_hash()),
+            (None, Some(mark)) => format!("Detail (marked {}, select another commit to compare)", mark.short_hash()),
+            (None, None) => "Detail".to_string(),
+        };
         f.render_widget(
-            Paragraph::new(section_title_line("Detail"))
+            Paragraph::new(section_title_line(&detail_title))
                 .style(Style::default().bg(SECTION_TITLE_BG).fg(SECTION_TITLE_FG)),
             layout[4],
         );
 
-        let detail_lines = format_diff_lines(&self.ctrl.detail, layout[5].width);
+        // Comparing can touch many files, so they get a pane to jump between.
+        let mut detail_area = layout[5];
+        self.files_area = None;
+        if compare.is_some() && !self.ctrl.files.is_empty() {
+            let cols = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Percentage(25), Constraint::Min(1)])
+                .split(layout[5]);
+            let items: Vec<ListItem> = self.ctrl.files.iter().map(|(path, _)| ListItem::new(path.clone())).collect();
+            self.files_state.select(Some(self.ctrl.file_idx));
+            let list = List::new(items)
+                .block(Block::default().borders(Borders::RIGHT).border_style(Style::default().fg(Color::DarkGray)))
+                .highlight_style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD));
+            f.render_stateful_widget(list, cols[0], &mut self.files_state);
+            self.files_area = Some(cols[0]);
+            detail_area = cols[1];
+        }
+
+        let scroll = self.ctrl.detail_scroll as usize;
+        let visible = scroll..scroll + detail_area.height as usize;
+        let detail_lines = if self.ctrl.side_by_side {
+            format_diff_side_by_side(&self.ctrl.detail, detail_area.width)
+        } else {
+            self.syntax.diff_lines(self.ctrl.detail_gen, &self.ctrl.detail, detail_area.width, visible)
+        };
         let detail = Paragraph::new(Text::from(detail_lines)).block(Block::default());
-        f.render_widget(detail.scroll((self.ctrl.detail_scroll, 0)), layout[5]);
-        self.detail_area = Some(layout[5]);
+        f.render_widget(detail.scroll((self.ctrl.detail_scroll, 0)), detail_area);
+        self.detail_area = Some(detail_area);
+
+        if let Some(picker) = &self.ctrl.picker {
+            let area = centered_rect(60, 60, f.size());
+            f.render_widget(Clear, area);
+            let block = Block::default()
+                .borders(Borders::ALL)
+                .title(" Browse ref or range (Tab: complete, Enter: open, Esc: cancel) ")
+                .border_style(Style::default().fg(Color::Cyan));
+            let inner = block.inner(area);
+            f.render_widget(block, area);
+            let rows = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Length(1), Constraint::Min(1)])
+                .split(inner);
+            f.render_widget(Paragraph::new(format!("> {}", picker.input)), rows[0]);
+            f.set_cursor(rows[0].x + 2 + picker.input.len() as u16, rows[0].y);
+            let items: Vec<ListItem> = picker
+                .matches()
+                .into_iter()
+                .map(|r| ListItem::new(Line::from(Span::styled(r.name.clone(), ref_style(r.kind)))))
+                .collect();
+            let mut state = ListState::default();
+            state.select(Some(picker.selected));
+            let list = List::new(items).highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+            f.render_stateful_widget(list, rows[1], &mut state);
+        }
+
+        let hash = self.ctrl.focus_commit().map(|c| c.short_hash().to_string()).unwrap_or_default();
+        match &self.action_popup {
+            Some(ActionPopup::Menu) => {
+                let area = centered_rect(50, 40, f.size());
+                f.render_widget(Clear, area);
+                let lines: Vec<Line> = CommitAction::ALL
+                    .iter()
+                    .map(|a| {
+                        Line::from(vec![
+                            Span::styled(format!(" {}  ", a.key()), Style::default().fg(Color::Yellow)),
+                            Span::raw(a.label()),
+                        ])
+                    })
+                    .collect();
+                let block = Block::default()
+                    .borders(Borders::ALL)
+                    .title(format!(" Actions on {} (Esc: cancel) ", hash))
+                    .border_style(Style::default().fg(Color::Cyan));
+                f.render_widget(Paragraph::new(lines).block(block), area);
+            }
+            Some(ActionPopup::Name(action, name)) => {
+                let area = centered_rect(50, 10, f.size());
+                f.render_widget(Clear, area);
+                let kind = if *action == CommitAction::Tag { "tag" } else { "branch" };
+                let block = Block::default()
+                    .borders(Borders::ALL)
+                    .title(format!(" New {} at {} (Enter: create, Esc: cancel) ", kind, hash))
+                    .border_style(Style::default().fg(Color::Cyan));
+                let inner = block.inner(area);
+                f.render_widget(Paragraph::new(format!("> {}", name)).block(block), area);
+                f.set_cursor(inner.x + 2 + name.len() as u16, inner.y);
+            }
+            Some(ActionPopup::Confirm(action)) => {
+                let area = centered_rect(50, 7, f.size());
+                f.render_widget(Clear, area);
+                let text = vec![
+                    Line::from(vec![
+                        Span::raw(format!("{} to ", action.label())),
+                        Span::styled(hash, Style::default().add_modifier(Modifier::BOLD).fg(Color::White)),
+                        Span::raw("?"),
+                    ]),
+                    Line::from(Span::styled("(y) Yes / (N) No", Style::default().fg(Color::DarkGray))),
+                ];
+                let block = Block::default()
+                    .borders(Borders::ALL)
+                    .title(" Confirmation ")
+                    .border_style(Style::default().fg(Color::DarkGray));
+                let p = Paragraph::new(text)
+                    .block(block)
+                    .alignment(ratatui::layout::Alignment::Center)
+                    .wrap(ratatui::widgets::Wrap { trim: true });
+                f.render_widget(p, area);
+            }
+            None => {}
+        }
+    }
+
+    // Scrolls the detail so the patch starting at `line` is on top; rules between hunks add rows.
+    fn scroll_to_line(&mut self, line: Option<usize>) {
+        let Some(line) = line else {
+            return;
+        };
+        let width = self.detail_area.map(|a| a.width).unwrap_or(80);
+        let before = &self.ctrl.detail[..line.min(self.ctrl.detail.len())];
+        let rows = if self.ctrl.side_by_side {
+            format_diff_side_by_side(before, width).len()
+        } else {
+            format_diff_lines(before, width).len()
+        };
+        self.ctrl.detail_scroll = rows.min(u16::MAX as usize) as u16;
+    }
+
+    fn run_action(&mut self, ctx: &mut AppContext, action: CommitAction, name: &str) -> anyhow::Result<Action> {
+        match self.ctrl.run_action(action, name) {
+            Ok(msg) => Ok(Action::Toast(msg)),
+            Err(err) => {
+                // A cherry-pick or revert stopped on conflicts; GitStage shows them with the banner.
+                if crate::git::operation_in_progress_at(&self.ctrl.repo_root).is_some() {
+                    if let Ok(state) = crate::ui::git_stage_ui::GitStageState::new(ctx) {
+                        return Ok(Action::Switch(Screen::GitStage(Box::new(state))));
+                    }
+                }
+                let first = err.to_string().lines().next().unwrap_or("").to_string();
+                Ok(Action::Toast(first))
+            }
+        }
+    }
+
+    fn action_key(&mut self, ctx: &mut AppContext, key: KeyEvent) -> anyhow::Result<Action> {
+        let Some(popup) = self.action_popup.take() else {
+            return Ok(Action::None);
+        };
+        match popup {
+            ActionPopup::Menu => {
+                let KeyCode::Char(c) = key.code else {
+                    if key.code != KeyCode::Esc {
+                        self.action_popup = Some(ActionPopup::Menu);
+                    }
+                    return Ok(Action::None);
+                };
+                match CommitAction::ALL.into_iter().find(|a| a.key() == c) {
+                    Some(action) if action.needs_name() => self.action_popup = Some(ActionPopup::Name(action, String::new())),
+                    Some(action) if action.needs_confirm() => self.action_popup = Some(ActionPopup::Confirm(action)),
+                    Some(action) => return self.run_action(ctx, action, ""),
+                    None if c == 'q' => {}
+                    None => self.action_popup = Some(ActionPopup::Menu),
+                }
+            }
+            ActionPopup::Name(action, mut name) => match key.code {
+                KeyCode::Esc => {}
+                KeyCode::Enter if name.trim().is_empty() => {
+                    self.action_popup = Some(ActionPopup::Name(action, name));
+                    return Ok(Action::Toast("Name is empty".to_string()));
+                }
+                KeyCode::Enter => return self.run_action(ctx, action, name.trim()),
+                _ => {
+                    match key.code {
+                        KeyCode::Backspace => {
+                            name.pop();
+                        }
+                        KeyCode::Char(c) if !c.is_control() && !c.is_whitespace() => name.push(c),
+                        _ => {}
+                    }
+                    self.action_popup = Some(ActionPopup::Name(action, name));
+                }
+            },
+            ActionPopup::Confirm(action) => {
+                if matches!(key.code, KeyCode::Char('y') | KeyCode::Char('Y')) {
+                    return self.run_action(ctx, action, "");
+                }
+            }
+        }
+        Ok(Action::None)
+    }
+
+    fn picker_key(&mut self, key: KeyEvent) -> anyhow::Result<Action> {
+        let Some(picker) = self.ctrl.picker.as_mut() else {
+            return Ok(Action::None);
+        };
+        match key.code {
+            KeyCode::Esc => self.ctrl.picker = None,
+            KeyCode::Down if picker.selected + 1 < picker.matches().len() => picker.selected += 1,
+            KeyCode::Up => picker.selected = picker.selected.saturating_sub(1),
+            KeyCode::Tab => picker.complete(),
+            KeyCode::Backspace => {
+                picker.input.pop();
+                picker.selected = 0;
+            }
+            KeyCode::Char(c) if !c.is_control() => {
+                picker.input.push(c);
+                picker.selected = 0;
+            }
+            KeyCode::Enter => {
+                if let Err(err) = self.ctrl.pick_ref() {
+                    return Ok(Action::Toast(err.to_string()));
+                }
+            }
+            _ => {}
+        }
+        Ok(Action::None)
     }
 
     pub fn on_key(&mut self, ctx: &mut AppContext, key: KeyEvent) -> anyhow::Result<Action> {
+        if self.ctrl.picker.is_some() {
+            return self.picker_key(key);
+        }
+        if self.action_popup.is_some() {
+            return self.action_key(ctx, key);
+        }
         if self.input_mode {
             match key.code {
                 KeyCode::Esc => self.input_mode = false,
+                // A search that stopped at its budget reads on; otherwise Enter closes the input.
+                KeyCode::Enter if self.ctrl.searching => self.ctrl.apply_filter()?,
                 KeyCode::Enter => self.input_mode = false,
                 KeyCode::Backspace => {
                     self.ctrl.filter.pop();
@@ -137,10 +443,12 @@ impl GitHistoryState {
         }
 
         match key.code {
+            KeyCode::Esc if self.ctrl.mark.is_some() => self.ctrl.clear_mark()?,
             KeyCode::Char('q') | KeyCode::Esc | KeyCode::Left => {
-                return Ok(Action::Switch(Screen::Main(Box::new(
-                    crate::ui::main_ui::MainState::new(ctx)?,
-                ))));
+                return Ok(match std::mem::replace(&mut self.origin, HistoryOrigin::Main) {
+                    HistoryOrigin::Main => Action::Switch(Screen::Main(Box::new(crate::ui::main_ui::MainState::new(ctx)?))),
+                    HistoryOrigin::Blame(blame) => Action::Switch(Screen::Blame(blame)),
+                });
             }
             KeyCode::Char('c') | KeyCode::Char('C') => {
                 match crate::ui::git_stage_ui::GitStageState::new(ctx) {
@@ -151,6 +459,41 @@ impl GitHistoryState {
             KeyCode::Char('/') => {
                 self.input_mode = true;
             }
+            KeyCode::Char('b') => {
+                if let Err(err) = self.ctrl.open_ref_picker() {
+                    return Ok(Action::Toast(err.to_string()));
+                }
+            }
+            KeyCode::Char('a') if self.ctrl.focus_commit().is_some() => {
+                self.action_popup = Some(ActionPopup::Menu);
+            }
+            KeyCode::Char('m') => self.ctrl.toggle_mark()?,
+            KeyCode::Char('n') => {
+                let line = self.ctrl.step_file(1);
+                self.scroll_to_line(line);
+            }
+            KeyCode::Char('N') => {
+                let line = self.ctrl.step_file(-1);
+                self.scroll_to_line(line);
+            }
+            KeyCode::Char('i') => {
+                let Some(base) = self.ctrl.focus_commit().map(|c| c.hash.clone()) else {
+                    return Ok(Action::None);
+                };
+                match crate::ui::rebase_ui::RebaseState::new(&base) {
+                    Ok(state) => return Ok(Action::Switch(Screen::Rebase(Box::new(state)))),
+                    Err(err) => return Ok(Action::Toast(err.to_string())),
+                }
+            }
+            KeyCode::Char('t') => {
+                ctx.config.history_relative_dates = !ctx.config.history_relative_dates;
+                ctx.save()?;
+                self.ctrl.relative_dates = ctx.config.history_relative_dates;
+            }
+            KeyCode::Char('S') => {
+                self.ctrl.side_by_side = toggle_side_by_side(ctx)?;
+                self.ctrl.detail_scroll = 0;
+            }
             KeyCode::Char('j')
                 if key
                     .modifiers
@@ -204,6 +547,18 @@ impl GitHistoryState {
             }
         }
 
+        if let Some(area) = self.files_area {
+            if area.contains(mouse_pos(&me)) {
+                let line = match me.kind {
+                    MouseEventKind::Down(_) => self.ctrl.select_file(self.files_state.offset() + (me.row - area.y) as usize),
+                    MouseEventKind::ScrollDown => self.ctrl.step_file(1),
+                    MouseEventKind::ScrollUp => self.ctrl.step_file(-1),
+                    _ => None,
+                };
+                self.scroll_to_line(line);
+            }
+        }
+
         if let Some(area) = self.detail_area {
             if area.contains(mouse_pos(&me)) {
                 if matches!(me.kind, MouseEventKind::ScrollDown) {
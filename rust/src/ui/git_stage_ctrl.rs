@@ -1,20 +1,61 @@
-use std::path::{Path};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
 use crate::app::AppContext;
-use crate::git::{self, GitItem, GitItemKind};
-use crate::system::{system};
+use crate::git::{self, DiffHunk, GitItem, GitItemKind};
+use crate::system::system;
+use crate::trash;
+use crate::ui::common::{diff_display_row, side_by_side_row};
+use crate::ui::highlight::next_generation;
 use crate::util::{file_size, strip_ansi};
 
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum HunkAction {
+    Stage,
+    Unstage,
+    Discard,
+}
+
+// Commits a staged change can become a `fixup!` for.
+pub struct FixupPicker {
+    pub upstream: Option<String>,
+    // Commits ahead of `upstream`, or recent history when `all` is set.
+    pub commits: Vec<(String, String)>,
+    pub selected: usize,
+    pub all: bool,
+    pub autosquash: bool,
+}
+
+const FIXUP_RECENT: &str = "-n30";
+
 pub struct GitStageCtrl {
     pub items: Vec<GitItem>,
     pub selected_idx: Option<usize>,
     pub content: Vec<String>,
+    // Generation of `content`, for the syntax cache.
+    pub content_gen: u64,
     pub content_scroll: u16,
+    // Unmodified `git diff` lines, used to build patches for hunk actions.
+    pub raw: Vec<String>,
+    pub hunks: Vec<DiffHunk>,
+    pub hunk_idx: usize,
+    // Line selection inside the focused hunk; indices point into `raw`.
+    pub line_mode: bool,
+    pub line_cursor: usize,
+    pub selected_lines: BTreeSet<usize>,
+    pub side_by_side: bool,
+    // Where discarded content is copied first; see `trash`.
+    pub trash_root: PathBuf,
+    // Files marked with `m`, e.g. to stash only those.
+    pub marked: BTreeSet<String>,
+    pub fixup: Option<FixupPicker>,
 }
 
 impl GitStageCtrl {
-    pub fn new(_ctx: &AppContext) -> anyhow::Result<Self> {
+    pub fn new(ctx: &AppContext) -> anyhow::Result<Self> {
         let items = git::build_git_items()?;
-        Self::with_items(items)
+        let mut ctrl = Self::with_items(items)?;
+        ctrl.side_by_side = ctx.config.diff_side_by_side;
+        Ok(ctrl)
     }
 
     pub fn with_items(items: Vec<GitItem>) -> anyhow::Result<Self> {
@@ -26,7 +67,18 @@ impl GitStageCtrl {
             items,
             selected_idx: first_selectable,
             content: vec!["< Nothing to display >".to_string()],
+            content_gen: 0,
             content_scroll: 0,
+            raw: Vec::new(),
+            hunks: Vec::new(),
+            hunk_idx: 0,
+            line_mode: false,
+            line_cursor: 0,
+            selected_lines: BTreeSet::new(),
+            side_by_side: false,
+            trash_root: trash::trash_root(),
+            marked: BTreeSet::new(),
+            fixup: None,
         };
         let _ = ctrl.load_content();
         Ok(ctrl)
@@ -34,11 +86,58 @@ impl GitStageCtrl {
 
     pub fn refresh(&mut self) -> anyhow::Result<()> {
         self.items = git::build_git_items()?;
+        self.retain_marks();
         self.selected_idx = self.first_selectable();
         self.load_content()?;
         Ok(())
     }
 
+    // Reload the status but stay on the same file (and hunk position) when it still exists.
+    pub fn refresh_keep_focus(&mut self) -> anyhow::Result<()> {
+        let focus = self.selected_idx
+            .and_then(|i| self.items.get(i))
+            .map(|item| (item.path.clone(), item.status.clone()));
+        let hunk_idx = self.hunk_idx;
+        self.items = git::build_git_items()?;
+        self.retain_marks();
+        self.selected_idx = focus
+            .and_then(|(path, status)| {
+                self.items.iter().position(|item| {
+                    item.kind == GitItemKind::Entry && item.path == path && item.status == status
+                })
+            })
+            .or_else(|| self.first_selectable());
+        self.load_content()?;
+        if !self.hunks.is_empty() {
+            self.hunk_idx = hunk_idx.min(self.hunks.len() - 1);
+            self.scroll_to_hunk();
+        }
+        Ok(())
+    }
+
+    fn retain_marks(&mut self) {
+        let items = &self.items;
+        self.marked.retain(|m| items.iter().any(|i| i.path.as_ref() == Some(m)));
+    }
+
+    pub fn is_marked(&self, item: &GitItem) -> bool {
+        item.kind == GitItemKind::Entry && item.path.as_ref().is_some_and(|p| self.marked.contains(p))
+    }
+
+    pub fn toggle_mark(&mut self) {
+        if let Some(name) = self.focus_file_name() {
+            if !self.marked.remove(&name) {
+                self.marked.insert(name);
+            }
+        }
+    }
+
+    // Marked files as absolute paths, for commands run from the repo root.
+    pub fn marked_paths(&self) -> Vec<String> {
+        let cwd = std::env::current_dir().unwrap_or_default();
+        self.marked.iter().map(|m| cwd.join(m).to_string_lossy().to_string()).collect()
+    }
+
     pub fn focus_file_name(&self) -> Option<String> {
         let idx = self.selected_idx?;
         let item = self.items.get(idx)?;
@@ -74,34 +173,282 @@ impl GitStageCtrl {
         Ok(())
     }
 
+    pub fn focus_status(&self) -> String {
+        self.selected_idx
+            .and_then(|i| self.items.get(i))
+            .and_then(|x| x.status.clone())
+            .unwrap_or_default()
+    }
+
     pub fn load_content(&mut self) -> anyhow::Result<()> {
+        self.raw.clear();
+        self.hunks.clear();
+        self.hunk_idx = 0;
+        self.line_mode = false;
+        self.selected_lines.clear();
         if let Some(name) = self.focus_file_name() {
-            let status = self.selected_idx
-                .and_then(|i| self.items.get(i))
-                .and_then(|x| x.status.clone())
-                .unwrap_or_default();
+            let status = self.focus_status();
+            // Untracked and conflicted files are shown as they are on disk.
+            let raw_file = status == "?" || status == "!";
+            let is_diff = !Path::new(&name).is_dir() && !raw_file;
             let out_res = if Path::new(&name).is_dir() {
                 Ok(format!("{} is folder", name))
-            } else if status == "?" {
+            } else if raw_file {
                 Ok(std::fs::read_to_string(&name)
                     .unwrap_or_else(|_| format!("No utf8 file[size:{}]", file_size(&name))))
-            } else if status == "s" {
-                system(&format!("git diff --color --staged \"{}\"", name))
             } else {
-                system(&format!("git diff --color \"{}\"", name))
+                git::file_diff(&name, status == "s")
             };
-            
-            let out = match out_res {
-                Ok(o) => o,
-                Err(e) => format!("Error loading content: {}", e),
-            };
-            self.content = strip_ansi(&out).replace('\t', "    ").lines().map(|s| s.to_string()).collect();
+
+            match out_res {
+                Ok(out) if is_diff => {
+                    // Split on '\n' only so CRLF files keep their '\r' for `git apply`.
+                    let body = out.strip_suffix('\n').unwrap_or(&out);
+                    if !body.is_empty() {
+                        self.raw = body.split('\n').map(|s| s.to_string()).collect();
+                    }
+                    self.hunks = git::parse_diff_hunks(&self.raw);
+                    self.content = self.raw
+                        .iter()
+                        .map(|l| strip_ansi(l).replace('\t', "    "))
+                        .collect();
+                }
+                Ok(out) => {
+                    self.content = strip_ansi(&out).replace('\t', "    ").lines().map(|s| s.to_string()).collect();
+                }
+                Err(e) => {
+                    self.content = vec![format!("Error loading content: {}", e)];
+                }
+            }
         } else {
             self.content = vec!["< Nothing to display >".to_string()];
         }
+        self.content_gen = next_generation();
         Ok(())
     }
 
+    pub fn focus_hunk(&self) -> Option<&DiffHunk> {
+        self.hunks.get(self.hunk_idx)
+    }
+
+    pub fn next_hunk(&mut self) {
+        if self.hunk_idx + 1 < self.hunks.len() {
+            self.hunk_idx += 1;
+        }
+        self.leave_line_mode();
+        self.scroll_to_hunk();
+    }
+
+    pub fn prev_hunk(&mut self) {
+        self.hunk_idx = self.hunk_idx.saturating_sub(1);
+        self.leave_line_mode();
+        self.scroll_to_hunk();
+    }
+
+    fn is_change_line(&self, idx: usize) -> bool {
+        self.raw
+            .get(idx)
+            .is_some_and(|l| l.starts_with('+') || l.starts_with('-'))
+    }
+
+    fn change_lines(&self) -> Vec<usize> {
+        match self.focus_hunk() {
+            Some(h) => (h.start + 1..h.end).filter(|&i| self.is_change_line(i)).collect(),
+            None => Vec::new(),
+        }
+    }
+
+    pub fn toggle_line_mode(&mut self) {
+        if self.line_mode {
+            self.leave_line_mode();
+            return;
+        }
+        if let Some(&first) = self.change_lines().first() {
+            self.line_mode = true;
+            self.line_cursor = first;
+            self.selected_lines.clear();
+        }
+    }
+
+    pub fn leave_line_mode(&mut self) {
+        self.line_mode = false;
+        self.selected_lines.clear();
+    }
+
+    // Moves the line cursor to the next/previous +/- line; `extend` selects both ends.
+    pub fn move_line(&mut self, forward: bool, extend: bool) {
+        let lines = self.change_lines();
+        let target = if forward {
+            lines.iter().find(|&&i| i > self.line_cursor)
+        } else {
+            lines.iter().rev().find(|&&i| i < self.line_cursor)
+        };
+        if let Some(&target) = target {
+            if extend {
+                self.selected_lines.insert(self.line_cursor);
+                self.selected_lines.insert(target);
+            }
+            self.line_cursor = target;
+        }
+    }
+
+    pub fn toggle_line(&mut self) {
+        if !self.line_mode || !self.is_change_line(self.line_cursor) {
+            return;
+        }
+        if !self.selected_lines.remove(&self.line_cursor) {
+            self.selected_lines.insert(self.line_cursor);
+        }
+    }
+
+    // Rendered row of `content[idx]`; line selection always uses the unified view.
+    pub fn display_row(&self, idx: usize) -> usize {
+        if self.side_by_side && !self.line_mode {
+            side_by_side_row(&self.content, idx)
+        } else {
+            diff_display_row(&self.content, idx)
+        }
+    }
+
+    fn scroll_to_hunk(&mut self) {
+        if let Some(hunk) = self.focus_hunk() {
+            let row = self.display_row(hunk.start);
+            self.content_scroll = row.min(u16::MAX as usize) as u16;
+        }
+    }
+
+    // Applies the focused hunk to the index or worktree depending on `action`.
+    pub fn apply_hunk(&mut self, action: HunkAction) -> anyhow::Result<String> {
+        let status = self.focus_status();
+        let Some(hunk) = self.focus_hunk() else {
+            return Ok("No hunk to apply".to_string());
+        };
+        let (cached, reverse, done) = match (action, status.as_str()) {
+            (HunkAction::Stage, "") => (true, false, "Staged hunk"),
+            (HunkAction::Unstage, "s") => (true, true, "Unstaged hunk"),
+            (HunkAction::Discard, "") => (false, true, "Discarded hunk"),
+            (HunkAction::Stage, _) => return Ok("Hunk is already staged".to_string()),
+            (HunkAction::Unstage, _) => return Ok("Hunk is not staged".to_string()),
+            (HunkAction::Discard, _) => return Ok("Unstage the hunk before discarding it".to_string()),
+        };
+        let patch = if self.line_mode {
+            let mut lines = self.selected_lines.clone();
+            if lines.is_empty() {
+                lines.insert(self.line_cursor);
+            }
+            match git::hunk_patch_for_lines(&self.raw, hunk, &lines, reverse) {
+                Some(patch) => patch,
+                None => return Ok("No line selected".to_string()),
+            }
+        } else {
+            git::hunk_patch(&self.raw, hunk)
+        };
+        let done = if self.line_mode { done.replace("hunk", "lines") } else { done.to_string() };
+        let root = git::repo_root()?;
+        if action == HunkAction::Discard {
+            let path = self.focus_file_name().map(|n| std::env::current_dir().unwrap_or_default().join(n));
+            trash::save_patch(&self.trash_root, &root, &path.unwrap_or_else(|| root.clone()), &patch)?;
+        }
+        git::apply_patch_at(&root, &patch, cached, reverse)?;
+        self.refresh_keep_focus()?;
+        Ok(done)
+    }
+
+    pub fn has_staged(&self) -> bool {
+        self.items.iter().any(|i| i.kind == GitItemKind::Entry && i.status.as_deref() == Some("s"))
+    }
+
+    // Opens the fixup picker on the commits ahead of the upstream, or recent ones without any.
+    pub fn open_fixup_picker(&mut self) -> anyhow::Result<()> {
+        let root = git::repo_root()?;
+        let upstream = git::get_tracking_branch();
+        let ahead = match &upstream {
+            Some(upstream) if git::commit_gap("HEAD", upstream)? > 0 => {
+                git::log_subjects_at(&root, &[&format!("{}..HEAD", upstream)])?
+            }
+            _ => Vec::new(),
+        };
+        let all = ahead.is_empty();
+        let commits = if all { git::log_subjects_at(&root, &[FIXUP_RECENT])? } else { ahead };
+        self.fixup = Some(FixupPicker { upstream, commits, selected: 0, all, autosquash: false });
+        Ok(())
+    }
+
+    // Switches between the commits ahead of the upstream and recent history.
+    pub fn toggle_fixup_range(&mut self) -> anyhow::Result<()> {
+        let root = git::repo_root()?;
+        let Some(picker) = self.fixup.as_mut() else {
+            return Ok(());
+        };
+        let Some(upstream) = picker.upstream.clone() else {
+            return Ok(());
+        };
+        picker.all = !picker.all;
+        let range = format!("{}..HEAD", upstream);
+        picker.commits = git::log_subjects_at(&root, &[if picker.all { FIXUP_RECENT } else { &range }])?;
+        picker.selected = 0;
+        Ok(())
+    }
+
+    pub fn create_fixup(&mut self) -> anyhow::Result<String> {
+        let Some(picker) = self.fixup.take() else {
+            return Ok(String::new());
+        };
+        let Some((hash, subject)) = picker.commits.get(picker.selected).cloned() else {
+            return Ok("No commit selected".to_string());
+        };
+        let root = git::repo_root()?;
+        // Squashing into a pushed commit would rewrite what the upstream already has.
+        if let Some(upstream) = picker.upstream.as_deref().filter(|_| picker.autosquash) {
+            if git::is_ancestor_at(&root, &hash, upstream) {
+                return Err(anyhow::anyhow!("{} is already in {}; not squashing into pushed history", &hash[..7], upstream));
+            }
+        }
+        git::fixup_commit_at(&root, &hash)?;
+        let mut msg = format!("fixup! {}", subject);
+        if picker.autosquash {
+            git::autosquash_at(&root, &hash)?;
+            msg = format!("Squashed into {} {}", &hash[..7], subject);
+        }
+        self.refresh()?;
+        Ok(msg)
+    }
+
+    // Throws away the focused file's changes (or the untracked file itself) after copying it
+    // to the trash. Staged new files are unstaged and removed.
+    pub fn discard_file(&mut self) -> anyhow::Result<String> {
+        let Some(name) = self.focus_file_name() else {
+            return Ok("No file selected".to_string());
+        };
+        let status = self.focus_status();
+        let root = git::repo_root()?;
+        let path = std::env::current_dir()?.join(&name);
+        trash::save_path(&self.trash_root, &root, &path)?;
+
+        let remove = |path: &Path| {
+            if path.is_dir() {
+                let _ = std::fs::remove_dir_all(path);
+            } else {
+                let _ = std::fs::remove_file(path);
+            }
+        };
+        let mut msg = format!("Reverted: {}", name);
+        if status == "?" {
+            if path.exists() {
+                remove(&path);
+                msg = format!("Deleted: {}", name);
+            }
+        } else if system(&format!("git checkout HEAD -- \"{}\"", name)).is_err() {
+            let _ = system(&format!("git reset HEAD \"{}\"", name));
+            if path.exists() {
+                remove(&path);
+                msg = format!("Deleted (Staged New): {}", name);
+            }
+        }
+        self.refresh()?;
+        Ok(format!("{} (Z: trash)", msg))
+    }
+
     fn first_selectable(&self) -> Option<usize> {
         self.items
             .iter()
@@ -144,8 +491,7 @@ mod tests {
 
     #[test]
     fn test_git_stage_ctrl_navigation() {
-        let env = TestEnv::setup("test_git_stage_ctrl");
-        let _ = system("git init");
+        let env = TestEnv::repo("test_git_stage_ctrl");
         std::fs::write(env.root.join("file1"), "data").unwrap();
         let _ = system("git add file1");
         
@@ -167,4 +513,122 @@ mod tests {
         ctrl.prev().unwrap();
         assert_eq!(ctrl.selected_idx, Some(1));
     }
+
+    #[test]
+    fn test_git_stage_ctrl_stage_single_hunk() {
+        let env = TestEnv::repo("test_git_stage_ctrl_hunk");
+        let base: Vec<String> = (1..=20).map(|i| format!("line{}", i)).collect();
+        std::fs::write(env.root.join("file1"), base.join("\n") + "\n").unwrap();
+        let _ = system("git add file1");
+
+        let mut changed = base.clone();
+        changed[1] = "line2 changed".to_string();
+        changed[17] = "line18 changed".to_string();
+        std::fs::write(env.root.join("file1"), changed.join("\n") + "\n").unwrap();
+
+        let items = vec![
+            GitItem { label: "< Modified >".to_string(), status: None, kind: GitItemKind::Header, path: None },
+            GitItem { label: " M file1".to_string(), status: Some("".to_string()), kind: GitItemKind::Entry, path: Some("file1".to_string()) },
+        ];
+        let mut ctrl = GitStageCtrl::with_items(items).unwrap();
+        assert_eq!(ctrl.hunks.len(), 2);
+
+        ctrl.next_hunk();
+        assert_eq!(ctrl.hunk_idx, 1);
+        ctrl.apply_hunk(HunkAction::Stage).unwrap();
+
+        let staged = system("git diff --staged").unwrap();
+        assert!(staged.contains("+line18 changed"));
+        assert!(!staged.contains("+line2 changed"));
+        let unstaged = system("git diff").unwrap();
+        assert!(unstaged.contains("+line2 changed"));
+        assert!(!unstaged.contains("+line18 changed"));
+    }
+
+    #[test]
+    fn test_git_stage_ctrl_stage_selected_lines() {
+        let env = TestEnv::repo("test_git_stage_ctrl_lines");
+        std::fs::write(env.root.join("file1"), "a\nb\nc\n").unwrap();
+        let _ = system("git add file1");
+        std::fs::write(env.root.join("file1"), "a\nb1\nb2\nc\n").unwrap();
+
+        let items = vec![
+            GitItem { label: " M file1".to_string(), status: Some("".to_string()), kind: GitItemKind::Entry, path: Some("file1".to_string()) },
+        ];
+        let mut ctrl = GitStageCtrl::with_items(items).unwrap();
+        ctrl.toggle_line_mode();
+        assert!(ctrl.line_mode);
+        assert_eq!(ctrl.raw[ctrl.line_cursor], "-b");
+
+        // Select "-b" and "+b1", leaving "+b2" unstaged.
+        ctrl.move_line(true, true);
+        assert_eq!(ctrl.raw[ctrl.line_cursor], "+b1");
+        ctrl.apply_hunk(HunkAction::Stage).unwrap();
+
+        let index = system("git show :file1").unwrap();
+        assert_eq!(index, "a\nb1\nc");
+        let unstaged = system("git diff").unwrap();
+        assert!(unstaged.contains("+b2"));
+        assert!(!unstaged.contains("-b\n"));
+    }
+
+    #[test]
+    fn test_git_stage_ctrl_fixup_ahead_of_upstream() {
+        let env = TestEnv::repo("test_git_stage_ctrl_fixup");
+        std::fs::write(env.root.join("a.txt"), "a\n").unwrap();
+        let _ = system("git add . && git commit -q -m base && git branch upstream && git branch -q -u upstream");
+        for name in ["c1", "c2"] {
+            std::fs::write(env.root.join(format!("{}.txt", name)), "v1\n").unwrap();
+            let _ = system(&format!("git add . && git commit -q -m {}", name));
+        }
+        std::fs::write(env.root.join("c1.txt"), "v2\n").unwrap();
+        let _ = system("git add c1.txt");
+
+        let mut ctrl = GitStageCtrl::with_items(git::build_git_items().unwrap()).unwrap();
+        assert!(ctrl.has_staged());
+        ctrl.open_fixup_picker().unwrap();
+        let picker = ctrl.fixup.as_mut().unwrap();
+        assert!(!picker.all);
+        assert_eq!(picker.commits.iter().map(|c| c.1.as_str()).collect::<Vec<_>>(), ["c2", "c1"]);
+        picker.selected = 1;
+        picker.autosquash = true;
+        ctrl.toggle_fixup_range().unwrap();
+        assert_eq!(ctrl.fixup.as_ref().unwrap().commits.len(), 3);
+        ctrl.toggle_fixup_range().unwrap();
+        let picker = ctrl.fixup.as_mut().unwrap();
+        picker.selected = 1;
+        assert!(ctrl.create_fixup().unwrap().starts_with("Squashed into"));
+
+        assert_eq!(system("git log --format=%s").unwrap(), "c2\nc1\nbase");
+        assert_eq!(system("git show HEAD~1:c1.txt").unwrap(), "v2");
+        assert!(!ctrl.has_staged());
+
+        // `base` is in the upstream already, so autosquashing into it is refused.
+        ctrl.open_fixup_picker().unwrap();
+        ctrl.toggle_fixup_range().unwrap();
+        let picker = ctrl.fixup.as_mut().unwrap();
+        picker.selected = 2;
+        picker.autosquash = true;
+        assert!(ctrl.create_fixup().is_err());
+        assert_eq!(system("git log --format=%s").unwrap(), "c2\nc1\nbase");
+    }
+
+    #[test]
+    fn test_git_stage_ctrl_discard_keeps_copy_in_trash() {
+        let env = TestEnv::repo("test_git_stage_ctrl_discard");
+        std::fs::write(env.root.join("new.txt"), "draft\n").unwrap();
+
+        let items = vec![
+            GitItem { label: "?? new.txt".to_string(), status: Some("?".to_string()), kind: GitItemKind::Entry, path: Some("new.txt".to_string()) },
+        ];
+        let mut ctrl = GitStageCtrl::with_items(items).unwrap();
+        ctrl.trash_root = env.root.join("trash");
+        assert!(ctrl.discard_file().unwrap().starts_with("Deleted: new.txt"));
+        assert!(!env.root.join("new.txt").exists());
+
+        let entries = trash::list(&ctrl.trash_root).unwrap();
+        assert_eq!(entries.len(), 1);
+        trash::restore(&entries[0]).unwrap();
+        assert_eq!(std::fs::read_to_string(env.root.join("new.txt")).unwrap(), "draft\n");
+    }
 }
@@ -1,17 +1,27 @@
-use std::path::{Path};
 use std::time::Instant;
 
 use crossterm::event::{KeyCode, KeyEvent, MouseEvent, MouseEventKind};
 use ratatui::layout::{Constraint, Direction, Layout, Margin, Rect};
-use ratatui::style::{Color, Style};
-use ratatui::text::Text;
-use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span, Text};
+use ratatui::widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph};
 
 use crate::app::{open_in_editor, AppContext};
 use crate::git::{self, GitItemKind};
 use crate::system::{app_log, system, system_stream};
-use crate::ui::common::{Action, Screen, mouse_pos, is_double_click, format_diff_lines, with_terminal_pause};
-use crate::ui::git_stage_ctrl::GitStageCtrl;
+use crate::ui::common::{Action, Screen, centered_rect, mouse_pos, is_double_click, format_diff_lines, format_diff_side_by_side, toggle_side_by_side, with_terminal_pause};
+use crate::ui::git_stage_ctrl::{GitStageCtrl, HunkAction};
+use crate::ui::highlight::SyntaxCache;
+use crate::ui::blame_ui::{BlameOrigin, BlameState};
+use crate::ui::conflict_ui::ConflictState;
+use crate::ui::operation_bar::OperationBar;
+use crate::ui::stash_ui::{StashOrigin, StashState};
+use crate::ui::trash_ui::TrashState;
+
+pub enum PendingDiscard {
+    File(String),
+    Hunk,
+}
 
 pub struct GitStageState {
     pub ctrl: GitStageCtrl,
@@ -19,6 +29,9 @@ pub struct GitStageState {
     pub list_area: Option<Rect>,
     pub content_area: Option<Rect>,
     pub last_click: Option<(Instant, usize)>,
+    pub syntax: SyntaxCache,
+    pub confirm: Option<PendingDiscard>,
+    pub operation: OperationBar,
 }
 
 impl GitStageState {
@@ -32,6 +45,9 @@ impl GitStageState {
             list_area: None,
             content_area: None,
             last_click: None,
+            syntax: SyntaxCache::new(),
+            confirm: None,
+            operation: OperationBar::load(),
         })
     }
 
@@ -39,16 +55,18 @@ impl GitStageState {
         let layout = Layout::default()
             .direction(Direction::Vertical)
             .constraints([Constraint::Percentage(35), Constraint::Percentage(65)])
-            .split(f.size());
+            .split(self.operation.render(f, f.size()));
         let items: Vec<ListItem> = self.ctrl
             .items
             .iter()
             .map(|item| {
                 let style = match item.kind {
                     GitItemKind::Header => Style::default().fg(Color::DarkGray),
+                    GitItemKind::Entry if self.ctrl.is_marked(item) => Style::default().fg(Color::Yellow),
                     GitItemKind::Entry => Style::default(),
                 };
-                ListItem::new(item.label.as_str()).style(style)
+                let mark = if self.ctrl.is_marked(item) { "* " } else { "" };
+                ListItem::new(format!("{}{}", mark, item.label)).style(style)
             })
             .collect();
         
@@ -59,21 +77,217 @@ impl GitStageState {
         f.render_stateful_widget(list, layout[0], &mut self.list_state);
         self.list_area = Some(layout[0]);
 
-        let diff_lines = format_diff_lines(&self.ctrl.content, layout[1].width);
+        let scroll = self.ctrl.content_scroll as usize;
+        let visible = scroll..scroll + layout[1].height as usize;
+        let mut diff_lines = if self.ctrl.side_by_side && !self.ctrl.line_mode {
+            format_diff_side_by_side(&self.ctrl.content, layout[1].width)
+        } else if self.ctrl.hunks.is_empty() {
+            format_diff_lines(&self.ctrl.content, layout[1].width)
+        } else {
+            self.syntax.diff_lines(self.ctrl.content_gen, &self.ctrl.content, layout[1].width, visible)
+        };
+        if let Some(hunk) = self.ctrl.focus_hunk() {
+            let row = self.ctrl.display_row(hunk.start);
+            if let Some(line) = diff_lines.get_mut(row) {
+                *line = line.clone().patch_style(Style::default().add_modifier(Modifier::REVERSED));
+            }
+        }
+        if self.ctrl.line_mode {
+            for &idx in &self.ctrl.selected_lines {
+                let row = self.ctrl.display_row(idx);
+                if let Some(line) = diff_lines.get_mut(row) {
+                    *line = line.clone().patch_style(Style::default().bg(Color::DarkGray));
+                }
+            }
+            let row = self.ctrl.display_row(self.ctrl.line_cursor);
+            if let Some(line) = diff_lines.get_mut(row) {
+                *line = line.clone().patch_style(Style::default().add_modifier(Modifier::REVERSED));
+            }
+            // Keep the line cursor inside the visible diff pane.
+            let height = layout[1].height.saturating_sub(1) as usize;
+            let scroll = self.ctrl.content_scroll as usize;
+            if row < scroll {
+                self.ctrl.content_scroll = row as u16;
+            } else if height > 0 && row >= scroll + height {
+                self.ctrl.content_scroll = (row + 1 - height) as u16;
+            }
+        }
+        let title = if self.ctrl.hunks.is_empty() {
+            "Diff".to_string()
+        } else if self.ctrl.line_mode {
+            format!(
+                "Diff [hunk {}/{}, lines: {} selected]",
+                self.ctrl.hunk_idx + 1,
+                self.ctrl.hunks.len(),
+                self.ctrl.selected_lines.len()
+            )
+        } else {
+            format!("Diff [hunk {}/{}]", self.ctrl.hunk_idx + 1, self.ctrl.hunks.len())
+        };
         let text = Text::from(diff_lines);
         let view = Paragraph::new(text)
-            .block(Block::default().title("Diff"));
+            .block(Block::default().title(title));
         f.render_widget(view.scroll((self.ctrl.content_scroll, 0)), layout[1]);
         self.content_area = Some(layout[1]);
+
+        if let Some(pending) = &self.confirm {
+            let area = centered_rect(50, 7, f.size());
+            f.render_widget(Clear, area);
+            let target = match pending {
+                PendingDiscard::File(name) => name.clone(),
+                PendingDiscard::Hunk if self.ctrl.line_mode => "the selected lines".to_string(),
+                PendingDiscard::Hunk => format!("hunk {}/{}", self.ctrl.hunk_idx + 1, self.ctrl.hunks.len()),
+            };
+            let text = vec![
+                Line::from(vec![
+                    Span::raw("Discard changes of "),
+                    Span::styled(target, Style::default().add_modifier(Modifier::BOLD).fg(Color::White)),
+                    Span::raw("?"),
+                ]),
+                Line::from(Span::styled(
+                    "A copy is kept in the trash (Z). (y) Yes / (N) No",
+                    Style::default().fg(Color::DarkGray),
+                )),
+            ];
+            let block = Block::default()
+                .borders(Borders::ALL)
+                .title(" Confirmation ")
+                .border_style(Style::default().fg(Color::DarkGray));
+            let p = Paragraph::new(text)
+                .block(block)
+                .alignment(ratatui::layout::Alignment::Center);
+            f.render_widget(p, area);
+        }
+        if let Some(picker) = &self.ctrl.fixup {
+            let area = centered_rect(70, 60, f.size());
+            f.render_widget(Clear, area);
+            let range = match (&picker.upstream, picker.all) {
+                (Some(upstream), false) => format!("ahead of {}", upstream),
+                _ => "recent commits".to_string(),
+            };
+            let squash = if picker.autosquash { "[x]" } else { "[ ]" };
+            let items: Vec<ListItem> = picker
+                .commits
+                .iter()
+                .map(|(hash, subject)| ListItem::new(format!("{} {}", &hash[..7], subject)))
+                .collect();
+            let mut state = ListState::default();
+            state.select(Some(picker.selected));
+            let list = List::new(items)
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .title(format!(" Fixup for: {} (a: range, Space: {} autosquash now, Enter, Esc) ", range, squash))
+                        .border_style(Style::default().fg(Color::Cyan)),
+                )
+                .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+            f.render_stateful_widget(list, area, &mut state);
+        }
+        self.operation.render_popup(f);
+    }
+
+    fn fixup_key(&mut self, key: KeyEvent) -> Action {
+        let Some(picker) = self.ctrl.fixup.as_mut() else {
+            return Action::None;
+        };
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => self.ctrl.fixup = None,
+            KeyCode::Char('j') | KeyCode::Down if picker.selected + 1 < picker.commits.len() => picker.selected += 1,
+            KeyCode::Char('k') | KeyCode::Up => picker.selected = picker.selected.saturating_sub(1),
+            KeyCode::Char(' ') => picker.autosquash = !picker.autosquash,
+            KeyCode::Char('a') => {
+                if let Err(err) = self.ctrl.toggle_fixup_range() {
+                    return Action::Toast(err.to_string());
+                }
+            }
+            KeyCode::Enter => {
+                return match self.ctrl.create_fixup() {
+                    Ok(msg) => Action::Toast(msg),
+                    Err(err) => {
+                        // A conflicting autosquash stops mid-rebase; the banner takes it from there.
+                        app_log(&format!("Fixup error: {}", err));
+                        let _ = self.ctrl.refresh();
+                        self.operation.refresh();
+                        let first = err.to_string().lines().next().unwrap_or("").to_string();
+                        Action::Toast(format!("Fixup failed: {}", first))
+                    }
+                };
+            }
+            _ => {}
+        }
+        Action::None
     }
 
     pub fn on_key(&mut self, ctx: &mut AppContext, key: KeyEvent) -> anyhow::Result<Action> {
+        if let Some(pending) = self.confirm.take() {
+            if !matches!(key.code, KeyCode::Char('y') | KeyCode::Char('Y')) {
+                return Ok(Action::None);
+            }
+            return Ok(match pending {
+                PendingDiscard::File(_) => match self.ctrl.discard_file() {
+                    Ok(msg) => Action::Toast(msg),
+                    Err(err) => {
+                        app_log(&format!("Discard error: {}", err));
+                        Action::Toast(format!("Discard failed: {}", err))
+                    }
+                },
+                PendingDiscard::Hunk => self.hunk_action(HunkAction::Discard),
+            });
+        }
+        if self.ctrl.fixup.is_some() {
+            return Ok(self.fixup_key(key));
+        }
+        if let Some(action) = self.operation.on_key(key) {
+            // The worktree may be clean afterwards; the list is then just left as it was.
+            let _ = self.ctrl.refresh();
+            return Ok(action);
+        }
+        if self.ctrl.line_mode {
+            match key.code {
+                KeyCode::Esc | KeyCode::Char('v') => {
+                    self.ctrl.leave_line_mode();
+                    return Ok(Action::None);
+                }
+                KeyCode::Char('j') | KeyCode::Down => {
+                    self.ctrl.move_line(true, false);
+                    return Ok(Action::None);
+                }
+                KeyCode::Char('k') | KeyCode::Up => {
+                    self.ctrl.move_line(false, false);
+                    return Ok(Action::None);
+                }
+                KeyCode::Char('J') => {
+                    self.ctrl.move_line(true, true);
+                    return Ok(Action::None);
+                }
+                KeyCode::Char('K') => {
+                    self.ctrl.move_line(false, true);
+                    return Ok(Action::None);
+                }
+                KeyCode::Char(' ') => {
+                    self.ctrl.toggle_line();
+                    return Ok(Action::None);
+                }
+                _ => {}
+            }
+        }
         match key.code {
             KeyCode::Char('q') | KeyCode::Esc => {
                 return Ok(Action::Switch(Screen::Main(Box::new(crate::ui::main_ui::MainState::new(ctx)?))));
             }
             KeyCode::Char('j') | KeyCode::Down => self.ctrl.next()?,
             KeyCode::Char('k') | KeyCode::Up => self.ctrl.prev()?,
+            KeyCode::Char('a') | KeyCode::Char('A') | KeyCode::Enter if self.ctrl.focus_status() == "!" => {
+                // Staging a conflicted file would commit its markers; resolve it first.
+                let focus = self.ctrl.focus_file_name().map(std::path::PathBuf::from);
+                return Ok(Action::Switch(Screen::Conflict(Box::new(ConflictState::new(focus.as_deref())?))));
+            }
+            KeyCode::Char('M') => {
+                return Ok(match ConflictState::new(None) {
+                    Ok(state) => Action::Switch(Screen::Conflict(Box::new(state))),
+                    Err(err) => Action::Toast(err.to_string()),
+                });
+            }
             KeyCode::Char('a') | KeyCode::Char('A') => {
                 if let Some(name) = self.ctrl.focus_file_name() {
                     system(&format!("git add \"{}\"", name))?;
@@ -87,42 +301,56 @@ impl GitStageState {
                 }
             }
             KeyCode::Char('d') | KeyCode::Char('D') => {
-                if let Some(idx) = self.ctrl.selected_idx {
-                    if let Some(item) = self.ctrl.items.get(idx) {
-                        if item.kind == GitItemKind::Entry {
-                            if let Some(name) = &item.path {
-                                let status = item.status.as_deref().unwrap_or("");
-                                let mut msg = format!("Reverted: {}", name);
-                                if status == "?" {
-                                    let path = Path::new(name);
-                                    if path.exists() {
-                                        if path.is_dir() {
-                                            let _ = std::fs::remove_dir_all(path);
-                                        } else {
-                                            let _ = std::fs::remove_file(path);
-                                        }
-                                        msg = format!("Deleted: {}", name);
-                                    }
-                                } else {
-                                    if let Err(_) = system(&format!("git checkout HEAD -- \"{}\"", name)) {
-                                        let _ = system(&format!("git reset HEAD \"{}\"", name));
-                                        let path = Path::new(name);
-                                        if path.exists() {
-                                            if path.is_dir() {
-                                                let _ = std::fs::remove_dir_all(path);
-                                            } else {
-                                                let _ = std::fs::remove_file(path);
-                                            }
-                                            msg = format!("Deleted (Staged New): {}", name);
-                                        }
-                                    }
-                                }
-                                self.ctrl.refresh()?;
-                                return Ok(Action::Toast(msg));
-                            }
-                        }
-                    }
+                self.confirm = self.ctrl.focus_file_name().map(PendingDiscard::File);
+            }
+            KeyCode::Char('Z') => {
+                return Ok(Action::Switch(Screen::Trash(Box::new(TrashState::new()?))));
+            }
+            KeyCode::Char('m') => {
+                self.ctrl.toggle_mark();
+                self.ctrl.next()?;
+            }
+            KeyCode::Char('B') => {
+                if let Some(name) = self.ctrl.focus_file_name() {
+                    return Ok(match BlameState::new(std::path::Path::new(&name), 0, BlameOrigin::GitStage) {
+                        Ok(state) => Action::Switch(Screen::Blame(Box::new(state))),
+                        Err(err) => Action::Toast(err.to_string()),
+                    });
+                }
+            }
+            KeyCode::Char('W') => {
+                let state = StashState::new(StashOrigin::GitStage, self.ctrl.marked_paths())?;
+                return Ok(Action::Switch(Screen::Stash(Box::new(state))));
+            }
+            KeyCode::Char('F') => {
+                if !self.ctrl.has_staged() {
+                    return Ok(Action::Toast("Stage changes first".to_string()));
+                }
+                if let Err(err) = self.ctrl.open_fixup_picker() {
+                    app_log(&format!("Fixup picker error: {}", err));
+                    let first = err.to_string().lines().next().unwrap_or("").to_string();
+                    return Ok(Action::Toast(format!("Cannot list commits: {}", first)));
                 }
+                if self.ctrl.fixup.as_ref().is_some_and(|p| p.commits.is_empty()) {
+                    self.ctrl.fixup = None;
+                    return Ok(Action::Toast("No commits to fix up".to_string()));
+                }
+            }
+            KeyCode::Char(']') => self.ctrl.next_hunk(),
+            KeyCode::Char('[') => self.ctrl.prev_hunk(),
+            KeyCode::Char('v') => self.ctrl.toggle_line_mode(),
+            KeyCode::Char('S') => {
+                self.ctrl.side_by_side = toggle_side_by_side(ctx)?;
+                self.ctrl.content_scroll = 0;
+            }
+            KeyCode::Char('s') => {
+                return Ok(self.hunk_action(HunkAction::Stage));
+            }
+            KeyCode::Char('u') => {
+                return Ok(self.hunk_action(HunkAction::Unstage));
+            }
+            KeyCode::Char('x') if !self.ctrl.hunks.is_empty() => {
+                self.confirm = Some(PendingDiscard::Hunk);
             }
             KeyCode::Char('i') | KeyCode::Char('I') => {
                 let target = self.ctrl.selected_idx.and_then(|idx| {
@@ -177,6 +405,16 @@ impl GitStageState {
         Ok(Action::None)
     }
 
+    fn hunk_action(&mut self, action: HunkAction) -> Action {
+        match self.ctrl.apply_hunk(action) {
+            Ok(msg) => Action::Toast(msg),
+            Err(err) => {
+                app_log(&format!("Hunk action error: {}", err));
+                Action::Toast(format!("git apply failed: {}", err))
+            }
+        }
+    }
+
     pub fn on_mouse(&mut self, _ctx: &mut AppContext, me: MouseEvent) -> anyhow::Result<Action> {
         if let Some(area) = self.list_area {
             if area.contains(mouse_pos(&me)) {
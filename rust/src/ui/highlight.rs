@@ -0,0 +1,519 @@
+use std::collections::HashMap;
+use std::ops::Range;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::OnceLock;
+
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use syntect::easy::HighlightLines;
+use syntect::highlighting::{Theme, ThemeSet};
+use syntect::parsing::{SyntaxReference, SyntaxSet};
+
+use crate::ui::common::format_diff_lines;
+
+// Lines highlighted per frame at most, so a jump to the end of a big file
+// is spread over several ticks instead of stalling the event loop.
+const HIGHLIGHT_BUDGET: usize = 2000;
+
+// Token pairs above this are not word-diffed; the LCS table would be too big.
+const WORD_DIFF_MAX_CELLS: usize = 40_000;
+
+pub const DIFF_ADD_BG: Color = Color::Rgb(0, 48, 0);
+pub const DIFF_DEL_BG: Color = Color::Rgb(64, 0, 0);
+pub const DIFF_ADD_EMPH_BG: Color = Color::Rgb(0, 110, 0);
+pub const DIFF_DEL_EMPH_BG: Color = Color::Rgb(140, 0, 0);
+
+struct Assets {
+    syntaxes: SyntaxSet,
+    theme: Theme,
+}
+
+fn assets() -> &'static Assets {
+    static ASSETS: OnceLock<Assets> = OnceLock::new();
+    ASSETS.get_or_init(|| {
+        let mut themes = ThemeSet::load_defaults();
+        let theme = themes
+            .themes
+            .remove("base16-ocean.dark")
+            .unwrap_or_default();
+        Assets {
+            syntaxes: SyntaxSet::load_defaults_newlines(),
+            theme,
+        }
+    })
+}
+
+fn syntax_for_path(path: &str) -> Option<&'static SyntaxReference> {
+    let assets = assets();
+    let p = Path::new(path);
+    let ext = p.extension().and_then(|s| s.to_str());
+    let name = p.file_name().and_then(|s| s.to_str());
+    ext.and_then(|e| assets.syntaxes.find_syntax_by_extension(e))
+        .or_else(|| name.and_then(|n| assets.syntaxes.find_syntax_by_extension(n)))
+        .filter(|s| s.name != "Plain Text")
+}
+
+fn diff_file_path(line: &str) -> Option<&str> {
+    let rest = line.strip_prefix("diff --git ")?;
+    rest.rfind(" b/").map(|pos| &rest[pos + 3..])
+}
+
+fn tokenize(text: &str) -> Vec<Range<usize>> {
+    fn kind(c: char) -> u8 {
+        if c.is_alphanumeric() || c == '_' {
+            1
+        } else if c.is_whitespace() {
+            2
+        } else {
+            3
+        }
+    }
+    let mut out = Vec::new();
+    let mut start: Option<(usize, u8)> = None;
+    for (i, c) in text.char_indices() {
+        let k = kind(c);
+        match start {
+            Some((_, prev)) if prev == k && k != 3 => {}
+            Some((st, _)) => {
+                out.push(st..i);
+                start = Some((i, k));
+            }
+            None => start = Some((i, k)),
+        }
+    }
+    if let Some((st, _)) = start {
+        out.push(st..text.len());
+    }
+    out
+}
+
+fn changed_ranges(tokens: &[Range<usize>], kept: &[bool]) -> Vec<Range<usize>> {
+    let mut out: Vec<Range<usize>> = Vec::new();
+    for (tok, &keep) in tokens.iter().zip(kept) {
+        if keep {
+            continue;
+        }
+        match out.last_mut() {
+            Some(last) if last.end == tok.start => last.end = tok.end,
+            _ => out.push(tok.clone()),
+        }
+    }
+    out
+}
+
+pub type WordRanges = Vec<Range<usize>>;
+
+// Byte ranges of `old` and `new` that differ, compared word by word.
+// None when the lines share no words, so emphasising them would only add noise.
+pub fn word_diff(old: &str, new: &str) -> Option<(WordRanges, WordRanges)> {
+    let a = tokenize(old);
+    let b = tokenize(new);
+    if a.len() * b.len() > WORD_DIFF_MAX_CELLS {
+        return None;
+    }
+    let (n, m) = (a.len(), b.len());
+    let mut lcs = vec![vec![0u32; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = if old[a[i].clone()] == new[b[j].clone()] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut kept_a = vec![false; n];
+    let mut kept_b = vec![false; m];
+    let mut shared_words = 0;
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        if old[a[i].clone()] == new[b[j].clone()] {
+            kept_a[i] = true;
+            kept_b[j] = true;
+            if !old[a[i].clone()].trim().is_empty() {
+                shared_words += 1;
+            }
+            i += 1;
+            j += 1;
+        } else if lcs[i + 1][j] >= lcs[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    if shared_words == 0 {
+        return None;
+    }
+    Some((changed_ranges(&a, &kept_a), changed_ranges(&b, &kept_b)))
+}
+
+// For every -/+ line of a hunk, the index of the line it replaces (or is replaced by).
+// A run of removed lines is paired in order with the run of added lines right after it.
+pub fn diff_pairs(lines: &[String]) -> Vec<Option<usize>> {
+    let mut pairs = vec![None; lines.len()];
+    let mut in_hunk = false;
+    let mut i = 0;
+    while i < lines.len() {
+        let line = &lines[i];
+        if line.starts_with("@@") {
+            in_hunk = true;
+            i += 1;
+            continue;
+        }
+        if in_hunk && line.starts_with('-') {
+            let mut dels = Vec::new();
+            let mut adds = Vec::new();
+            while i < lines.len() && (lines[i].starts_with('-') || lines[i].starts_with('\\')) {
+                if lines[i].starts_with('-') {
+                    dels.push(i);
+                }
+                i += 1;
+            }
+            while i < lines.len() && (lines[i].starts_with('+') || lines[i].starts_with('\\')) {
+                if lines[i].starts_with('+') {
+                    adds.push(i);
+                }
+                i += 1;
+            }
+            for (&d, &a) in dels.iter().zip(&adds) {
+                pairs[d] = Some(a);
+                pairs[a] = Some(d);
+            }
+            continue;
+        }
+        if !(line.starts_with(' ') || line.starts_with('+') || line.starts_with('\\')) {
+            in_hunk = false;
+        }
+        i += 1;
+    }
+    pairs
+}
+
+// Splits coloured pieces at the `emph` boundaries; the bool marks emphasised pieces.
+fn split_emphasis(
+    segs: &[(Option<Color>, String)],
+    emph: &[Range<usize>],
+) -> Vec<(Option<Color>, String, bool)> {
+    let mut out = Vec::new();
+    let mut pos = 0;
+    for (fg, text) in segs {
+        let (start, end) = (pos, pos + text.len());
+        let mut cuts = vec![start, end];
+        for r in emph {
+            for b in [r.start, r.end] {
+                if b > start && b < end && text.is_char_boundary(b - start) {
+                    cuts.push(b);
+                }
+            }
+        }
+        cuts.sort_unstable();
+        cuts.dedup();
+        for w in cuts.windows(2) {
+            let piece = text[w[0] - start..w[1] - start].to_string();
+            let is_emph = emph.iter().any(|r| r.start <= w[0] && w[1] <= r.end);
+            out.push((*fg, piece, is_emph));
+        }
+        pos = end;
+    }
+    out
+}
+
+pub type Segments = Vec<(Color, String)>;
+
+// A ctrl takes a fresh generation whenever it replaces text a SyntaxCache highlights,
+// so the cache notices a reload without rehashing every line on every frame.
+pub fn next_generation() -> u64 {
+    static NEXT: AtomicU64 = AtomicU64::new(1);
+    NEXT.fetch_add(1, Ordering::Relaxed)
+}
+
+// Incremental syntect state for one block of text (a file or a diff).
+// Lines are highlighted in order, only as far as the visible rows need.
+// For diffs it also keeps the word-diff ranges of the paired -/+ lines seen so far.
+pub struct SyntaxCache {
+    key: String,
+    gen: u64,
+    next: usize,
+    in_hunk: bool,
+    state: Option<HighlightLines<'static>>,
+    lines: Vec<Option<Segments>>,
+    pairs: Vec<Option<usize>>,
+    words: HashMap<usize, Option<Vec<Range<usize>>>>,
+}
+
+impl Default for SyntaxCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SyntaxCache {
+    pub fn new() -> Self {
+        Self {
+            key: String::new(),
+            gen: 0,
+            next: 0,
+            in_hunk: false,
+            state: None,
+            lines: Vec::new(),
+            pairs: Vec::new(),
+            words: HashMap::new(),
+        }
+    }
+
+    fn sync(&mut self, key: &str, gen: u64, lines: &[String]) {
+        if gen != self.gen || key != self.key || self.lines.len() != lines.len() {
+            self.key = key.to_string();
+            self.gen = gen;
+            self.next = 0;
+            self.in_hunk = false;
+            self.state = None;
+            self.lines = vec![None; lines.len()];
+            self.pairs.clear();
+            self.words.clear();
+        }
+    }
+
+    // Changed ranges of `lines[idx]` (without its +/- prefix) against its paired line.
+    fn word_ranges(&mut self, lines: &[String], idx: usize) -> Option<Vec<Range<usize>>> {
+        let other = self.pairs.get(idx).copied().flatten()?;
+        if !self.words.contains_key(&idx) {
+            let (del, add) = if idx < other { (idx, other) } else { (other, idx) };
+            let diff = word_diff(&lines[del][1..], &lines[add][1..]);
+            self.words.insert(del, diff.as_ref().map(|d| d.0.clone()));
+            self.words.insert(add, diff.map(|d| d.1));
+        }
+        self.words.get(&idx).cloned().flatten()
+    }
+
+    fn highlight(&mut self, code: &str) -> Option<Segments> {
+        let state = self.state.as_mut()?;
+        let text = format!("{}\n", code);
+        let ranges = state.highlight_line(&text, &assets().syntaxes).ok()?;
+        Some(
+            ranges
+                .into_iter()
+                .map(|(style, piece)| {
+                    let fg = style.foreground;
+                    (
+                        Color::Rgb(fg.r, fg.g, fg.b),
+                        piece.trim_end_matches('\n').to_string(),
+                    )
+                })
+                .filter(|(_, piece)| !piece.is_empty())
+                .collect(),
+        )
+    }
+
+    fn advance_file(&mut self, path: &str, lines: &[String], upto: usize) {
+        if self.next == 0 {
+            self.state = syntax_for_path(path).map(|s| HighlightLines::new(s, &assets().theme));
+        }
+        if self.state.is_none() {
+            return;
+        }
+        let end = upto.min(lines.len()).min(self.next + HIGHLIGHT_BUDGET);
+        while self.next < end {
+            self.lines[self.next] = self.highlight(&lines[self.next]);
+            self.next += 1;
+        }
+    }
+
+    fn advance_diff(&mut self, lines: &[String], upto: usize) {
+        let end = upto.min(lines.len()).min(self.next + HIGHLIGHT_BUDGET);
+        while self.next < end {
+            let line = &lines[self.next];
+            if let Some(path) = diff_file_path(line) {
+                self.state = syntax_for_path(path).map(|s| HighlightLines::new(s, &assets().theme));
+                self.in_hunk = false;
+            } else if line.starts_with("@@") {
+                self.in_hunk = true;
+            } else if self.in_hunk
+                && (line.starts_with(' ') || line.starts_with('+') || line.starts_with('-'))
+            {
+                self.lines[self.next] = self.highlight(&line[1..]);
+            } else if !line.starts_with('\\') {
+                self.in_hunk = false;
+            }
+            self.next += 1;
+        }
+    }
+
+    pub fn segments(&self, idx: usize) -> Option<&Segments> {
+        self.lines.get(idx).and_then(|s| s.as_ref())
+    }
+
+    // Plain file preview; `rows` is the visible window and `gen` the generation of `lines`.
+    pub fn file_lines(&mut self, path: &str, gen: u64, lines: &[String], rows: Range<usize>) -> Vec<Line<'static>> {
+        self.sync(path, gen, lines);
+        self.advance_file(path, lines, rows.end);
+        lines
+            .iter()
+            .enumerate()
+            .map(|(i, line)| match self.segments(i) {
+                Some(segs) if rows.contains(&i) => Line::from(
+                    segs.iter()
+                        .map(|(fg, text)| Span::styled(text.clone(), Style::default().fg(*fg)))
+                        .collect::<Vec<_>>(),
+                ),
+                _ => Line::from(line.clone()),
+            })
+            .collect()
+    }
+
+    // `format_diff_lines` with syntax colours on hunk bodies (the +/- colour moved to the background)
+    // and the changed words of paired -/+ lines emphasised.
+    pub fn diff_lines(&mut self, gen: u64, lines: &[String], width: u16, rows: Range<usize>) -> Vec<Line<'static>> {
+        self.sync("", gen, lines);
+        let mut out = format_diff_lines(lines, width);
+        let mut row = 0;
+        let mut first_hunk = true;
+        let mut visible = Vec::new();
+        for (i, line) in lines.iter().enumerate() {
+            if line.starts_with("@@") {
+                if !first_hunk {
+                    row += 1;
+                }
+                first_hunk = false;
+            }
+            if rows.contains(&row) {
+                visible.push((i, row));
+            }
+            row += 1;
+        }
+        if let Some(&(last, _)) = visible.last() {
+            self.advance_diff(lines, last + 1);
+        }
+        if self.pairs.len() != lines.len() {
+            self.pairs = diff_pairs(lines);
+        }
+        for (i, row) in visible {
+            let emph = self.word_ranges(lines, i);
+            let line = &lines[i];
+            let segs: Vec<(Option<Color>, String)> = match self.segments(i) {
+                Some(segs) => segs.iter().map(|(fg, t)| (Some(*fg), t.clone())).collect(),
+                None if emph.is_some() => vec![(None, line[1..].to_string())],
+                None => continue,
+            };
+            // With syntax colours the +/- state moves to the background, otherwise it stays the foreground.
+            let syntax = segs.iter().any(|(fg, _)| fg.is_some());
+            let (base, strong) = match (line.starts_with('+'), line.starts_with('-'), syntax) {
+                (true, _, true) => (Style::default().bg(DIFF_ADD_BG), Style::default().bg(DIFF_ADD_EMPH_BG)),
+                (_, true, true) => (Style::default().bg(DIFF_DEL_BG), Style::default().bg(DIFF_DEL_EMPH_BG)),
+                (true, _, false) => {
+                    let s = Style::default().fg(Color::Green);
+                    (s, s.add_modifier(Modifier::REVERSED))
+                }
+                (_, true, false) => {
+                    let s = Style::default().fg(Color::Red);
+                    (s, s.add_modifier(Modifier::REVERSED))
+                }
+                _ => (Style::default(), Style::default()),
+            };
+            let mut spans = vec![Span::styled(line[..1].to_string(), base)];
+            for (fg, text, is_emph) in split_emphasis(&segs, emph.as_deref().unwrap_or(&[])) {
+                let mut style = if is_emph { strong } else { base };
+                if let Some(fg) = fg {
+                    style = style.fg(fg);
+                }
+                spans.push(Span::styled(text, style));
+            }
+            out[row] = Line::from(spans);
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_lines_highlights_visible_hunk_body() {
+        let lines: Vec<String> = [
+            "diff --git a/src/main.rs b/src/main.rs",
+            "--- a/src/main.rs",
+            "+++ b/src/main.rs",
+            "@@ -1,2 +1,2 @@",
+            " fn main() {",
+            "-    let a = 1;",
+            "+    let a = 2;",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+        let mut cache = SyntaxCache::new();
+
+        let out = cache.diff_lines(1, &lines, 80, 0..3);
+        assert!(cache.segments(4).is_none());
+        assert_eq!(out[4].spans.len(), 1);
+
+        let out = cache.diff_lines(1, &lines, 80, 0..10);
+        assert!(cache.segments(4).is_some());
+        // A new generation is new content, highlighted from the top again.
+        cache.diff_lines(2, &lines, 80, 0..3);
+        assert!(cache.segments(4).is_none());
+        assert_eq!(out[0].spans[0].content, "diff --git a/src/main.rs b/src/main.rs");
+        assert!(out[4].spans.len() > 2);
+        assert_eq!(out[5].spans[0].style.bg, Some(DIFF_DEL_BG));
+        assert_eq!(out[6].spans[0].style.bg, Some(DIFF_ADD_BG));
+        let text: String = out[6].spans.iter().map(|s| s.content.as_ref()).collect();
+        assert_eq!(text, "+    let a = 2;");
+    }
+
+    #[test]
+    fn test_word_diff_marks_changed_words() {
+        let (old, new) = word_diff("let value = old_name(1);", "let value = new_name(1);").unwrap();
+        assert_eq!(old, vec![12..20]);
+        assert_eq!(new, vec![12..20]);
+        assert!(word_diff("alpha beta", "gamma delta").is_none());
+    }
+
+    #[test]
+    fn test_diff_pairs_matches_runs_in_order() {
+        let lines: Vec<String> = ["@@ -1,3 +1,2 @@", "-a1", "-a2", "+b1", " ctx", "+c1"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let pairs = diff_pairs(&lines);
+        assert_eq!(pairs[1], Some(3));
+        assert_eq!(pairs[3], Some(1));
+        assert_eq!(pairs[2], None);
+        assert_eq!(pairs[5], None);
+    }
+
+    #[test]
+    fn test_diff_lines_emphasises_changed_words_without_syntax() {
+        let lines: Vec<String> = [
+            "diff --git a/notes.unknownext b/notes.unknownext",
+            "@@ -1 +1 @@",
+            "-same old text",
+            "+same new text",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+        let mut cache = SyntaxCache::new();
+        let out = cache.diff_lines(1, &lines, 80, 0..10);
+        let emph: Vec<&str> = out[3]
+            .spans
+            .iter()
+            .filter(|s| s.style.add_modifier.contains(Modifier::REVERSED))
+            .map(|s| s.content.as_ref())
+            .collect();
+        assert_eq!(emph, vec!["new"]);
+        assert_eq!(out[3].spans[0].style.fg, Some(Color::Green));
+        assert_eq!(out[2].spans[0].style.fg, Some(Color::Red));
+    }
+
+    #[test]
+    fn test_file_lines_skips_unknown_extension() {
+        let lines = vec!["plain".to_string()];
+        let mut cache = SyntaxCache::new();
+        let out = cache.file_lines("notes.unknownext", 1, &lines, 0..10);
+        assert!(cache.segments(0).is_none());
+        assert_eq!(out[0].spans[0].content, "plain");
+    }
+}
@@ -12,6 +12,9 @@ use crate::app::{open_in_editor, AppContext};
 use crate::git;
 use crate::system::{app_log};
 use crate::ui::common::{Action, Screen, INPUT_PREFIX, mouse_pos, is_double_click, with_terminal_pause};
+use crate::ui::operation_bar::OperationBar;
+use crate::ui::blame_ui::{BlameOrigin, BlameState};
+use crate::ui::stash_ui::{StashOrigin, StashState};
 
 pub struct MainState {
     pub cwd: PathBuf,
@@ -27,6 +30,7 @@ pub struct MainState {
     pub registered_paths: Vec<String>,
     pub confirm_delete: bool,
     pub confirm_target: Option<String>,
+    pub operation: OperationBar,
 }
 
 #[derive(Clone)]
@@ -53,6 +57,7 @@ impl MainState {
             registered_paths,
             confirm_delete: false,
             confirm_target: None,
+            operation: OperationBar::default(),
         };
         state.refresh();
         Ok(state)
@@ -85,6 +90,7 @@ impl MainState {
         );
 
         self.list_state.select(Some(0));
+        self.operation.refresh();
     }
 
     fn focus_name(&self) -> Option<String> {
@@ -127,10 +133,11 @@ impl MainState {
 
     pub fn render(&mut self, f: &mut ratatui::Frame) {
         let cwd_str = self.cwd.to_string_lossy();
+        let area = self.operation.render(f, f.size());
         let layout = Layout::default()
             .direction(Direction::Horizontal)
             .constraints([Constraint::Percentage(75), Constraint::Percentage(25)])
-            .split(f.size());
+            .split(area);
 
         let left = Layout::default()
             .direction(Direction::Vertical)
@@ -221,6 +228,7 @@ impl MainState {
                 .alignment(ratatui::layout::Alignment::Center);
             f.render_widget(p, area);
         }
+        self.operation.render_popup(f);
     }
 
     pub fn on_key(&mut self, ctx: &mut AppContext, key: KeyEvent) -> anyhow::Result<Action> {
@@ -242,6 +250,10 @@ impl MainState {
             }
             return Ok(Action::None);
         }
+        if let Some(action) = self.operation.on_key(key) {
+            self.refresh();
+            return Ok(action);
+        }
 
         if self.input_mode {
             match key.code {
@@ -353,6 +365,20 @@ impl MainState {
                     }
                 }
             }
+            KeyCode::Char('B') => {
+                if let Some(path) = self.focus_editor_target().filter(|p| p.is_file()) {
+                    return Ok(match BlameState::new(&path, 0, BlameOrigin::Main) {
+                        Ok(state) => Action::Switch(Screen::Blame(Box::new(state))),
+                        Err(err) => Action::Toast(err.to_string()),
+                    });
+                }
+            }
+            KeyCode::Char('W') => {
+                match StashState::new(StashOrigin::Main, Vec::new()) {
+                    Ok(state) => return Ok(Action::Switch(Screen::Stash(Box::new(state)))),
+                    Err(err) => return Ok(Action::Toast(err.to_string())),
+                }
+            }
             KeyCode::Char('T') => {
                 match crate::ui::git_history_ui::GitHistoryState::new(ctx) {
                     Ok(state) => return Ok(Action::Switch(Screen::GitHistory(Box::new(state)))),
@@ -563,6 +589,7 @@ mod tests {
             registered_paths: Vec::new(),
             confirm_delete: false,
             confirm_target: None,
+            operation: OperationBar::default(),
         }
     }
 
@@ -638,6 +665,7 @@ mod tests {
             registered_paths: Vec::new(),
             confirm_delete: false,
             confirm_target: None,
+            operation: OperationBar::default(),
         };
 
         let _ = state.on_key(&mut ctx, KeyEvent::new(KeyCode::Char('E'), KeyModifiers::NONE));
@@ -1,4 +1,5 @@
 pub mod common;
+pub mod highlight;
 pub mod main_ui;
 pub mod main_ctrl;
 pub mod find_ui;
@@ -11,11 +12,23 @@ pub mod git_stage_ui;
 pub mod git_stage_ctrl;
 pub mod git_commit_ui;
 pub mod git_commit_ctrl;
+pub mod commit_editor;
 pub mod reg_list_ui;
 pub mod reg_list_ctrl;
 pub mod goto_ui;
 pub mod goto_ctrl;
 pub mod git_push_ui;
+pub mod trash_ui;
+pub mod trash_ctrl;
+pub mod stash_ui;
+pub mod stash_ctrl;
+pub mod conflict_ui;
+pub mod conflict_ctrl;
+pub mod operation_bar;
+pub mod rebase_ui;
+pub mod rebase_ctrl;
+pub mod blame_ui;
+pub mod blame_ctrl;
 
 use std::io::{self, Stdout};
 use std::sync::atomic::Ordering;
@@ -153,6 +166,11 @@ impl<'a> App<'a> {
             Screen::GitCommit(state) => state.render(f),
             Screen::RegList(state) => state.render(f),
             Screen::Goto(state) => state.render(f),
+            Screen::Trash(state) => state.render(f),
+            Screen::Stash(state) => state.render(f),
+            Screen::Conflict(state) => state.render(f),
+            Screen::Rebase(state) => state.render(f),
+            Screen::Blame(state) => state.render(f),
         }
 
         if let Some((msg, _)) = &self.toast {
@@ -190,6 +208,11 @@ impl<'a> App<'a> {
             Screen::GitCommit(state) => state.on_key(self.ctx, key)?,
             Screen::RegList(state) => state.on_key(self.ctx, key)?,
             Screen::Goto(state) => state.on_key(self.ctx, key)?,
+            Screen::Trash(state) => state.on_key(self.ctx, key)?,
+            Screen::Stash(state) => state.on_key(self.ctx, key)?,
+            Screen::Conflict(state) => state.on_key(self.ctx, key)?,
+            Screen::Rebase(state) => state.on_key(self.ctx, key)?,
+            Screen::Blame(state) => state.on_key(self.ctx, key)?,
         };
         match action {
             Action::None => Ok(false),
@@ -216,6 +239,11 @@ impl<'a> App<'a> {
             Screen::GitCommit(state) => state.on_mouse(self.ctx, me)?,
             Screen::RegList(state) => state.on_mouse(self.ctx, me)?,
             Screen::Goto(state) => state.on_mouse(self.ctx, me)?,
+            Screen::Trash(state) => state.on_mouse(self.ctx, me)?,
+            Screen::Stash(state) => state.on_mouse(self.ctx, me)?,
+            Screen::Conflict(state) => state.on_mouse(self.ctx, me)?,
+            Screen::Rebase(state) => state.on_mouse(self.ctx, me)?,
+            Screen::Blame(state) => state.on_mouse(self.ctx, me)?,
         };
         match action {
             Action::None => Ok(false),
@@ -0,0 +1,130 @@
+use std::path::PathBuf;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::layout::{Alignment, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, Paragraph};
+
+use crate::git::{self, GitOpAction, GitOperation};
+use crate::system::app_log;
+use crate::ui::common::{Action, centered_rect};
+
+// Banner for a rebase/merge/cherry-pick/revert/bisect in progress, shared by Main and GitStage.
+// Alt+C continues, Alt+S skips and Alt+A aborts (after a confirmation).
+#[derive(Default)]
+pub struct OperationBar {
+    pub root: Option<PathBuf>,
+    pub op: Option<GitOperation>,
+    pub confirm_abort: bool,
+}
+
+impl OperationBar {
+    // Looks at the repo of the current directory.
+    pub fn load() -> Self {
+        let mut bar = Self::default();
+        bar.refresh();
+        bar
+    }
+
+    pub fn refresh(&mut self) {
+        self.root = git::repo_root().ok();
+        self.op = self.root.as_ref().and_then(|root| git::operation_in_progress_at(root));
+    }
+
+    // Draws the banner on the top row and returns the rest of `area`.
+    pub fn render(&self, f: &mut ratatui::Frame, area: Rect) -> Rect {
+        let Some(op) = &self.op else {
+            return area;
+        };
+        let mut hint = " Alt+C: continue".to_string();
+        if op.action_args(GitOpAction::Skip).is_some() {
+            hint.push_str(", Alt+S: skip");
+        }
+        hint.push_str(", Alt+A: abort ");
+        let banner = Paragraph::new(Line::from(vec![
+            Span::styled(
+                format!(" {} in progress ", op.label),
+                Style::default().fg(Color::Black).bg(Color::Yellow).add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(hint, Style::default().fg(Color::Yellow)),
+        ]));
+        f.render_widget(banner, Rect { height: 1, ..area });
+        Rect {
+            y: area.y + 1,
+            height: area.height.saturating_sub(1),
+            ..area
+        }
+    }
+
+    // Drawn last so it stays on top of the screen.
+    pub fn render_popup(&self, f: &mut ratatui::Frame) {
+        let (true, Some(op)) = (self.confirm_abort, &self.op) else {
+            return;
+        };
+        let area = centered_rect(50, 7, f.size());
+        f.render_widget(Clear, area);
+        let text = vec![
+            Line::from(vec![
+                Span::raw("Abort "),
+                Span::styled(op.label.clone(), Style::default().add_modifier(Modifier::BOLD).fg(Color::White)),
+                Span::raw("?"),
+            ]),
+            Line::from(Span::styled("(y) Yes / (N) No", Style::default().fg(Color::DarkGray))),
+        ];
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .title(" Confirmation ")
+            .border_style(Style::default().fg(Color::DarkGray));
+        f.render_widget(Paragraph::new(text).block(block).alignment(Alignment::Center), area);
+    }
+
+    // Some(action) when the key was consumed; the caller should then reload its own state.
+    pub fn on_key(&mut self, key: KeyEvent) -> Option<Action> {
+        if self.confirm_abort {
+            self.confirm_abort = false;
+            if matches!(key.code, KeyCode::Char('y') | KeyCode::Char('Y')) {
+                return Some(self.run(GitOpAction::Abort));
+            }
+            return Some(Action::None);
+        }
+        self.op.as_ref()?;
+        if !key.modifiers.contains(KeyModifiers::ALT) {
+            return None;
+        }
+        match key.code {
+            KeyCode::Char('c') | KeyCode::Char('C') => Some(self.run(GitOpAction::Continue)),
+            KeyCode::Char('s') | KeyCode::Char('S') => Some(self.run(GitOpAction::Skip)),
+            KeyCode::Char('a') | KeyCode::Char('A') => {
+                self.confirm_abort = true;
+                Some(Action::None)
+            }
+            _ => None,
+        }
+    }
+
+    fn run(&mut self, action: GitOpAction) -> Action {
+        let (Some(root), Some(op)) = (self.root.clone(), self.op.clone()) else {
+            return Action::None;
+        };
+        let result = git::operation_action_at(&root, &op, action);
+        self.refresh();
+        let verb = match action {
+            GitOpAction::Continue => "continue",
+            GitOpAction::Skip => "skip",
+            GitOpAction::Abort => "abort",
+        };
+        match result {
+            Ok(_) => match &self.op {
+                Some(next) => Action::Toast(next.label.clone()),
+                None if action == GitOpAction::Abort => Action::Toast(format!("{} aborted", op.command())),
+                None => Action::Toast(format!("{} finished", op.command())),
+            },
+            Err(err) => {
+                app_log(&format!("{} {} error: {}", op.command(), verb, err));
+                let first = err.to_string().lines().next().unwrap_or("").to_string();
+                Action::Toast(format!("{} {} failed: {}", op.command(), verb, first))
+            }
+        }
+    }
+}
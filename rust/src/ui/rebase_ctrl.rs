@@ -0,0 +1,169 @@
+use std::path::PathBuf;
+
+use crate::git::{self, TodoAction, TodoItem};
+use crate::ui::commit_editor::CommitEditor;
+use crate::ui::highlight::next_generation;
+
+pub struct RebaseCtrl {
+    pub repo_root: PathBuf,
+    pub base: String,
+    pub base_label: String,
+    // Oldest first, as in a `git rebase -i` todo list.
+    pub items: Vec<TodoItem>,
+    pub selected_idx: usize,
+    pub detail: Vec<String>,
+    // Generation of `detail`, for the syntax cache.
+    pub detail_gen: u64,
+    pub detail_scroll: u16,
+    // Reword popup: the item being edited and its message.
+    pub reword: Option<(usize, CommitEditor)>,
+}
+
+impl RebaseCtrl {
+    pub fn new(base: &str) -> anyhow::Result<Self> {
+        Self::with_root(git::repo_root()?, base)
+    }
+
+    pub fn with_root(repo_root: PathBuf, base: &str) -> anyhow::Result<Self> {
+        let base = git::run_git_at("Rebase", &repo_root, &["rev-parse", base])?.trim().to_string();
+        let items = git::rebase_commits_at(&repo_root, &base)?;
+        if items.is_empty() {
+            return Err(anyhow::anyhow!("No commits above {} to rebase", &base[..7]));
+        }
+        let subject = git::run_git_at("Rebase", &repo_root, &["log", "-1", "--format=%s", &base]).unwrap_or_default();
+        let mut ctrl = Self {
+            base_label: format!("{} {}", &base[..7], subject.trim()),
+            repo_root,
+            base,
+            items,
+            selected_idx: 0,
+            detail: Vec::new(),
+            detail_gen: 0,
+            detail_scroll: 0,
+            reword: None,
+        };
+        ctrl.load_detail();
+        Ok(ctrl)
+    }
+
+    pub fn focus_item(&self) -> Option<&TodoItem> {
+        self.items.get(self.selected_idx)
+    }
+
+    pub fn load_detail(&mut self) {
+        self.detail_scroll = 0;
+        let Some(item) = self.focus_item() else {
+            return;
+        };
+        self.detail = match git::commit_detail_at(&self.repo_root, &item.hash) {
+            Ok(lines) => lines,
+            Err(err) => vec![format!("Error loading detail: {}", err)],
+        };
+        self.detail_gen = next_generation();
+    }
+
+    pub fn next(&mut self) {
+        if self.selected_idx + 1 < self.items.len() {
+            self.selected_idx += 1;
+            self.load_detail();
+        }
+    }
+
+    pub fn prev(&mut self) {
+        if self.selected_idx > 0 {
+            self.selected_idx -= 1;
+            self.load_detail();
+        }
+    }
+
+    // Moves the focused commit one step later (`down`) or earlier in the plan.
+    pub fn move_item(&mut self, down: bool) {
+        let idx = self.selected_idx;
+        let target = if down { idx + 1 } else { idx.wrapping_sub(1) };
+        if target < self.items.len() {
+            self.items.swap(idx, target);
+            self.selected_idx = target;
+        }
+    }
+
+    pub fn set_action(&mut self, action: TodoAction) {
+        if let Some(item) = self.items.get_mut(self.selected_idx) {
+            item.action = action;
+            if action != TodoAction::Reword {
+                item.message = None;
+            }
+        }
+    }
+
+    // Opens the message editor for the focused commit, starting from its planned or current message.
+    pub fn start_reword(&mut self) -> anyhow::Result<()> {
+        let Some(item) = self.focus_item() else {
+            return Ok(());
+        };
+        let message = match &item.message {
+            Some(message) => message.clone(),
+            None => git::commit_message_at(&self.repo_root, &item.hash)?,
+        };
+        self.reword = Some((self.selected_idx, CommitEditor::from_message(&message)));
+        Ok(())
+    }
+
+    pub fn finish_reword(&mut self) -> bool {
+        let Some((idx, editor)) = self.reword.take() else {
+            return false;
+        };
+        if editor.is_empty() {
+            self.reword = Some((idx, editor));
+            return false;
+        }
+        if let Some(item) = self.items.get_mut(idx) {
+            item.action = TodoAction::Reword;
+            item.message = Some(editor.message());
+        }
+        true
+    }
+
+    pub fn summary(&self) -> String {
+        let kept = self.items.iter().filter(|i| i.action != TodoAction::Drop).count();
+        format!("{} commit(s), {} kept, onto {}", self.items.len(), kept, self.base_label)
+    }
+
+    pub fn run(&self) -> anyhow::Result<String> {
+        git::run_rebase_plan_at(&self.repo_root, &self.base, &self.items)?;
+        Ok(format!("Rebased onto {}", self.base_label))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::system::system;
+    use crate::ui::common::TestEnv;
+
+    #[test]
+    fn test_rebase_ctrl_reorder_and_reword() {
+        let env = TestEnv::repo("test_rebase_ctrl");
+        for name in ["base", "c1", "c2"] {
+            std::fs::write(env.root.join(name), name).unwrap();
+            let _ = system(&format!("git add . && git commit -q -m {}", name));
+        }
+        let mut ctrl = RebaseCtrl::with_root(git::repo_root().unwrap(), "HEAD~2").unwrap();
+        assert!(ctrl.base_label.ends_with("base"));
+        ctrl.move_item(false);
+        assert_eq!(ctrl.selected_idx, 0);
+        ctrl.move_item(true);
+        assert_eq!(ctrl.items[1].subject, "c1");
+
+        ctrl.start_reword().unwrap();
+        let (_, editor) = ctrl.reword.as_mut().unwrap();
+        editor.set_subject("c1 new");
+        assert!(ctrl.finish_reword());
+        assert_eq!(ctrl.items[1].action, TodoAction::Reword);
+        assert_eq!(ctrl.summary(), format!("2 commit(s), 2 kept, onto {}", ctrl.base_label));
+
+        ctrl.run().unwrap();
+        let log = system("git log --format=%s").unwrap();
+        assert_eq!(log.lines().collect::<Vec<_>>(), ["c1 new", "c2", "base"]);
+        assert!(RebaseCtrl::with_root(git::repo_root().unwrap(), "HEAD").is_err());
+    }
+}
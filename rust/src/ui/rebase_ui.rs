@@ -0,0 +1,242 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseEvent, MouseEventKind};
+use ratatui::layout::{Constraint, Direction, Layout, Margin, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span, Text};
+use ratatui::widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph};
+
+use crate::app::AppContext;
+use crate::git::TodoAction;
+use crate::system::app_log;
+use crate::ui::commit_editor::{ruler_line, BODY_LIMIT, SUBJECT_LIMIT};
+use crate::ui::common::{Action, Screen, centered_rect, mouse_pos};
+use crate::ui::highlight::SyntaxCache;
+use crate::ui::rebase_ctrl::RebaseCtrl;
+
+fn action_color(action: TodoAction) -> Color {
+    match action {
+        TodoAction::Pick => Color::Reset,
+        TodoAction::Reword => Color::Cyan,
+        TodoAction::Squash | TodoAction::Fixup => Color::Yellow,
+        TodoAction::Drop => Color::DarkGray,
+    }
+}
+
+pub struct RebaseState {
+    pub ctrl: RebaseCtrl,
+    pub list_state: ListState,
+    pub list_area: Option<Rect>,
+    pub detail_area: Option<Rect>,
+    pub syntax: SyntaxCache,
+    pub confirm: bool,
+}
+
+impl RebaseState {
+    pub fn new(base: &str) -> anyhow::Result<Self> {
+        Ok(Self {
+            ctrl: RebaseCtrl::new(base)?,
+            list_state: ListState::default(),
+            list_area: None,
+            detail_area: None,
+            syntax: SyntaxCache::new(),
+            confirm: false,
+        })
+    }
+
+    pub fn render(&mut self, f: &mut ratatui::Frame) {
+        let list_height = (self.ctrl.items.len() as u16 + 1).clamp(3, 12);
+        let layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(1),
+                Constraint::Length(list_height),
+                Constraint::Min(3),
+            ])
+            .split(f.size());
+
+        let title = Paragraph::new(Line::from(Span::styled(
+            " >> Rebase plan (p/r/s/f/d: pick/reword/squash/fixup/drop, J/K: move, Enter: run, q: cancel)",
+            Style::default().fg(Color::Green).add_modifier(Modifier::BOLD),
+        )));
+        f.render_widget(title, layout[0]);
+
+        let items: Vec<ListItem> = self
+            .ctrl
+            .items
+            .iter()
+            .map(|item| {
+                let subject = match &item.message {
+                    Some(message) => message.lines().next().unwrap_or("").to_string(),
+                    None => item.subject.clone(),
+                };
+                let mut style = Style::default().fg(action_color(item.action));
+                if item.action == TodoAction::Drop {
+                    style = style.add_modifier(Modifier::CROSSED_OUT);
+                }
+                ListItem::new(format!("{:<7} {} {}", item.action.word(), &item.hash[..7], subject)).style(style)
+            })
+            .collect();
+        self.list_state.select(Some(self.ctrl.selected_idx));
+        let list = List::new(items)
+            .block(Block::default().title(self.ctrl.summary()))
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        f.render_stateful_widget(list, layout[1], &mut self.list_state);
+        self.list_area = Some(layout[1]);
+
+        let scroll = self.ctrl.detail_scroll as usize;
+        let visible = scroll..scroll + layout[2].height as usize;
+        let lines = self.syntax.diff_lines(self.ctrl.detail_gen, &self.ctrl.detail, layout[2].width, visible);
+        let detail = Paragraph::new(Text::from(lines))
+            .block(Block::default().title("Commit"))
+            .scroll((self.ctrl.detail_scroll, 0));
+        f.render_widget(detail, layout[2]);
+        self.detail_area = Some(layout[2]);
+
+        if self.confirm {
+            let area = centered_rect(50, 7, f.size());
+            f.render_widget(Clear, area);
+            let text = vec![
+                Line::from(vec![Span::raw("Rebase "), Span::styled(self.ctrl.summary(), Style::default().add_modifier(Modifier::BOLD).fg(Color::White)), Span::raw("?")]),
+                Line::from(Span::styled("(y) Yes / (N) No", Style::default().fg(Color::DarkGray))),
+            ];
+            let block = Block::default()
+                .borders(Borders::ALL)
+                .title(" Confirmation ")
+                .border_style(Style::default().fg(Color::DarkGray));
+            let p = Paragraph::new(text)
+                .block(block)
+                .alignment(ratatui::layout::Alignment::Center)
+                .wrap(ratatui::widgets::Wrap { trim: true });
+            f.render_widget(p, area);
+        }
+
+        if let Some((_, editor)) = &self.ctrl.reword {
+            let area = centered_rect(80, 50, f.size());
+            f.render_widget(Clear, area);
+            let inner_height = area.height.saturating_sub(2) as usize;
+            let mut lines = vec![ruler_line(editor.subject(), SUBJECT_LIMIT), Line::from("")];
+            let body_rows = inner_height.saturating_sub(2);
+            let skip = editor.row.saturating_sub(body_rows);
+            lines.extend(editor.body().iter().skip(skip).take(body_rows).map(|l| ruler_line(l, BODY_LIMIT)));
+            let block = Block::default()
+                .borders(Borders::ALL)
+                .title(" Reword (Tab: subject/body, Ctrl+S: save, Esc: cancel) ")
+                .border_style(Style::default().fg(Color::Cyan));
+            f.render_widget(Paragraph::new(Text::from(lines)).block(block), area);
+            let y = if editor.row == 0 { 0 } else { editor.row + 1 - skip };
+            f.set_cursor(area.x + 1 + editor.cursor_width() as u16, area.y + 1 + y as u16);
+        }
+    }
+
+    fn back(ctx: &mut AppContext) -> anyhow::Result<Action> {
+        Ok(Action::Switch(Screen::GitHistory(Box::new(crate::ui::git_history_ui::GitHistoryState::new(ctx)?))))
+    }
+
+    pub fn on_key(&mut self, ctx: &mut AppContext, key: KeyEvent) -> anyhow::Result<Action> {
+        if let Some((_, editor)) = &mut self.ctrl.reword {
+            let ctrl_key = key.modifiers.contains(KeyModifiers::CONTROL);
+            match key.code {
+                KeyCode::Esc => self.ctrl.reword = None,
+                KeyCode::Char('s') if ctrl_key => {
+                    if !self.ctrl.finish_reword() {
+                        return Ok(Action::Toast("Commit subject is empty".to_string()));
+                    }
+                }
+                _ => {
+                    editor.edit_key(key);
+                }
+            }
+            return Ok(Action::None);
+        }
+        if self.confirm {
+            self.confirm = false;
+            if !matches!(key.code, KeyCode::Char('y') | KeyCode::Char('Y')) {
+                return Ok(Action::None);
+            }
+            return match self.ctrl.run() {
+                Ok(_) => Self::back(ctx),
+                Err(err) => {
+                    // A stop for conflicts leaves the rebase in progress; GitStage shows it with the banner.
+                    app_log(&format!("Rebase plan error: {}", err));
+                    if crate::git::operation_in_progress_at(&self.ctrl.repo_root).is_some() {
+                        if let Ok(state) = crate::ui::git_stage_ui::GitStageState::new(ctx) {
+                            return Ok(Action::Switch(Screen::GitStage(Box::new(state))));
+                        }
+                    }
+                    let first = err.to_string().lines().next().unwrap_or("").to_string();
+                    Ok(Action::Toast(format!("Rebase stopped: {}", first)))
+                }
+            };
+        }
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Self::back(ctx),
+            KeyCode::Char('j') | KeyCode::Down => self.ctrl.next(),
+            KeyCode::Char('k') | KeyCode::Up => self.ctrl.prev(),
+            KeyCode::Char('J') => self.ctrl.move_item(true),
+            KeyCode::Char('K') => self.ctrl.move_item(false),
+            KeyCode::PageDown => self.ctrl.detail_scroll = self.ctrl.detail_scroll.saturating_add(10),
+            KeyCode::PageUp => self.ctrl.detail_scroll = self.ctrl.detail_scroll.saturating_sub(10),
+            KeyCode::Char('p') => self.ctrl.set_action(TodoAction::Pick),
+            KeyCode::Char('r') => {
+                if let Err(err) = self.ctrl.start_reword() {
+                    app_log(&format!("Reword error: {}", err));
+                    return Ok(Action::Toast(format!("Cannot reword: {}", err)));
+                }
+            }
+            KeyCode::Char('s') => self.ctrl.set_action(TodoAction::Squash),
+            KeyCode::Char('f') => self.ctrl.set_action(TodoAction::Fixup),
+            KeyCode::Char('d') => self.ctrl.set_action(TodoAction::Drop),
+            KeyCode::Enter => match crate::git::check_rebase_plan(&self.ctrl.items) {
+                Ok(()) => self.confirm = true,
+                Err(err) => return Ok(Action::Toast(err.to_string())),
+            },
+            _ => {}
+        }
+        Ok(Action::None)
+    }
+
+    pub fn on_mouse(&mut self, _ctx: &mut AppContext, me: MouseEvent) -> anyhow::Result<Action> {
+        if let Some(area) = self.list_area {
+            if area.contains(mouse_pos(&me)) {
+                match me.kind {
+                    MouseEventKind::Down(_) => {
+                        let inner = area.inner(&Margin { horizontal: 0, vertical: 1 });
+                        if me.row >= inner.y && me.row < inner.y + inner.height {
+                            let idx = (me.row - inner.y) as usize + self.list_state.offset();
+                            while self.ctrl.selected_idx < idx && self.ctrl.selected_idx + 1 < self.ctrl.items.len() {
+                                self.ctrl.next();
+                            }
+                            while self.ctrl.selected_idx > idx {
+                                self.ctrl.prev();
+                            }
+                        }
+                    }
+                    MouseEventKind::ScrollDown => self.ctrl.next(),
+                    MouseEventKind::ScrollUp => self.ctrl.prev(),
+                    _ => {}
+                }
+            }
+        }
+        if let Some(area) = self.detail_area {
+            if area.contains(mouse_pos(&me)) {
+                if matches!(me.kind, MouseEventKind::ScrollDown) {
+                    self.ctrl.detail_scroll = self.ctrl.detail_scroll.saturating_add(3);
+                } else if matches!(me.kind, MouseEventKind::ScrollUp) {
+                    self.ctrl.detail_scroll = self.ctrl.detail_scroll.saturating_sub(3);
+                }
+            }
+        }
+        Ok(Action::None)
+    }
+}
+
+impl crate::ui::common::ScreenState for RebaseState {
+    fn render(&mut self, f: &mut ratatui::Frame) {
+        self.render(f);
+    }
+    fn on_key(&mut self, ctx: &mut AppContext, key: KeyEvent) -> anyhow::Result<Action> {
+        self.on_key(ctx, key)
+    }
+    fn on_mouse(&mut self, ctx: &mut AppContext, me: MouseEvent) -> anyhow::Result<Action> {
+        self.on_mouse(ctx, me)
+    }
+}
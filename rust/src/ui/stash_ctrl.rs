@@ -0,0 +1,149 @@
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::git::{self, StashEntry};
+use crate::ui::trash_ctrl::format_age;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum StashAction {
+    Apply,
+    Pop,
+    Drop,
+}
+
+impl StashAction {
+    pub fn verb(self) -> &'static str {
+        match self {
+            StashAction::Apply => "apply",
+            StashAction::Pop => "pop",
+            StashAction::Drop => "drop",
+        }
+    }
+}
+
+pub struct StashCtrl {
+    pub repo_root: PathBuf,
+    pub entries: Vec<StashEntry>,
+    pub selected_idx: usize,
+    pub content: Vec<String>,
+    pub content_scroll: u16,
+    // Files marked in GitStage (absolute paths); a new stash can be limited to them.
+    pub selected_paths: Vec<String>,
+}
+
+impl StashCtrl {
+    pub fn new(selected_paths: Vec<String>) -> anyhow::Result<Self> {
+        let mut ctrl = Self {
+            repo_root: git::repo_root()?,
+            entries: Vec::new(),
+            selected_idx: 0,
+            content: Vec::new(),
+            content_scroll: 0,
+            selected_paths,
+        };
+        ctrl.reload()?;
+        Ok(ctrl)
+    }
+
+    pub fn reload(&mut self) -> anyhow::Result<()> {
+        self.entries = git::stash_list_at(&self.repo_root)?;
+        self.selected_idx = self.selected_idx.min(self.entries.len().saturating_sub(1));
+        self.load_content();
+        Ok(())
+    }
+
+    pub fn focus_entry(&self) -> Option<&StashEntry> {
+        self.entries.get(self.selected_idx)
+    }
+
+    pub fn label(entry: &StashEntry) -> String {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+        format!(
+            "{:<10} {:>8}  [{}] {}",
+            entry.name,
+            format_age(now.saturating_sub(entry.time)),
+            entry.branch,
+            entry.message
+        )
+    }
+
+    fn load_content(&mut self) {
+        self.content_scroll = 0;
+        let Some(entry) = self.focus_entry() else {
+            self.content = vec!["< No stashes >".to_string()];
+            return;
+        };
+        self.content = match git::stash_diff_at(&self.repo_root, &entry.name) {
+            Ok(diff) => diff.replace('\t', "    ").lines().map(|l| l.to_string()).collect(),
+            Err(err) => vec![format!("< {} >", err)],
+        };
+    }
+
+    pub fn next(&mut self) {
+        if self.selected_idx + 1 < self.entries.len() {
+            self.selected_idx += 1;
+            self.load_content();
+        }
+    }
+
+    pub fn prev(&mut self) {
+        if self.selected_idx > 0 {
+            self.selected_idx -= 1;
+            self.load_content();
+        }
+    }
+
+    pub fn run(&mut self, action: StashAction) -> anyhow::Result<String> {
+        let Some(entry) = self.focus_entry().cloned() else {
+            return Ok("No stash selected".to_string());
+        };
+        let result = git::run_git_at("Stash", &self.repo_root, &["stash", action.verb(), &entry.name]);
+        // A conflicting apply/pop still changes the worktree, so always reload.
+        self.reload()?;
+        result?;
+        Ok(format!("Stash {}: {} {}", action.verb(), entry.name, entry.message))
+    }
+
+    pub fn push(&mut self, message: &str, include_untracked: bool, only_selected: bool) -> anyhow::Result<String> {
+        let paths: &[String] = if only_selected { &self.selected_paths } else { &[] };
+        let out = git::stash_push_at(&self.repo_root, message, include_untracked, paths)?;
+        if only_selected {
+            self.selected_paths.clear();
+        }
+        self.selected_idx = 0;
+        self.reload()?;
+        Ok(out.lines().next().unwrap_or("Stashed").to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::system::system;
+    use crate::ui::common::TestEnv;
+
+    #[test]
+    fn test_stash_ctrl_push_selected_and_pop() {
+        let env = TestEnv::repo("test_stash_ctrl");
+        std::fs::write(env.root.join("a.txt"), "a\n").unwrap();
+        std::fs::write(env.root.join("b.txt"), "b\n").unwrap();
+        let _ = system("git add . && git commit -q -m base");
+        std::fs::write(env.root.join("a.txt"), "a changed\n").unwrap();
+        std::fs::write(env.root.join("b.txt"), "b changed\n").unwrap();
+
+        let selected = vec![env.root.join("b.txt").to_string_lossy().to_string()];
+        let mut ctrl = StashCtrl::new(selected).unwrap();
+        assert!(ctrl.entries.is_empty());
+        ctrl.push("only b", false, true).unwrap();
+        assert_eq!(ctrl.entries.len(), 1);
+        assert!(StashCtrl::label(&ctrl.entries[0]).ends_with("only b"));
+        assert!(ctrl.content.iter().any(|l| l == "+b changed"));
+        assert!(ctrl.selected_paths.is_empty());
+        assert_eq!(std::fs::read_to_string(env.root.join("b.txt")).unwrap(), "b\n");
+        assert_eq!(std::fs::read_to_string(env.root.join("a.txt")).unwrap(), "a changed\n");
+
+        ctrl.run(StashAction::Pop).unwrap();
+        assert!(ctrl.entries.is_empty());
+        assert_eq!(std::fs::read_to_string(env.root.join("b.txt")).unwrap(), "b changed\n");
+    }
+}
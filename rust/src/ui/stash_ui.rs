@@ -0,0 +1,251 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseEvent, MouseEventKind};
+use ratatui::layout::{Constraint, Direction, Layout, Margin, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span, Text};
+use ratatui::widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph};
+
+use crate::app::AppContext;
+use crate::system::app_log;
+use crate::ui::common::{Action, Screen, centered_rect, format_diff_lines, mouse_pos};
+use crate::ui::stash_ctrl::{StashAction, StashCtrl};
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum StashOrigin {
+    Main,
+    GitStage,
+}
+
+// The "new stash" popup.
+pub struct StashInput {
+    pub message: String,
+    pub include_untracked: bool,
+    pub only_selected: bool,
+}
+
+pub struct StashState {
+    pub ctrl: StashCtrl,
+    pub origin: StashOrigin,
+    pub input: Option<StashInput>,
+    pub confirm_drop: bool,
+    pub list_state: ListState,
+    pub list_area: Option<Rect>,
+    pub content_area: Option<Rect>,
+}
+
+impl StashState {
+    pub fn new(origin: StashOrigin, selected_paths: Vec<String>) -> anyhow::Result<Self> {
+        Ok(Self {
+            ctrl: StashCtrl::new(selected_paths)?,
+            origin,
+            input: None,
+            confirm_drop: false,
+            list_state: ListState::default(),
+            list_area: None,
+            content_area: None,
+        })
+    }
+
+    pub fn render(&mut self, f: &mut ratatui::Frame) {
+        let layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(1),
+                Constraint::Percentage(30),
+                Constraint::Min(3),
+            ])
+            .split(f.size());
+
+        let title = Paragraph::new(Line::from(Span::styled(
+            " >> Stash (a: apply, p: pop, d: drop, n: new stash, q: back)",
+            Style::default().fg(Color::Green).add_modifier(Modifier::BOLD),
+        )));
+        f.render_widget(title, layout[0]);
+
+        let items: Vec<ListItem> = self.ctrl.entries.iter().map(|e| ListItem::new(StashCtrl::label(e))).collect();
+        self.list_state.select(if items.is_empty() { None } else { Some(self.ctrl.selected_idx) });
+        let list = List::new(items)
+            .block(Block::default().title("Stashes"))
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        f.render_stateful_widget(list, layout[1], &mut self.list_state);
+        self.list_area = Some(layout[1]);
+
+        let view = Paragraph::new(Text::from(format_diff_lines(&self.ctrl.content, layout[2].width)))
+            .block(Block::default().title("Diff"))
+            .scroll((self.ctrl.content_scroll, 0));
+        f.render_widget(view, layout[2]);
+        self.content_area = Some(layout[2]);
+
+        if self.confirm_drop {
+            let area = centered_rect(50, 7, f.size());
+            f.render_widget(Clear, area);
+            let name = self.ctrl.focus_entry().map(|e| e.name.clone()).unwrap_or_default();
+            let text = vec![
+                Line::from(vec![
+                    Span::raw("Drop "),
+                    Span::styled(name, Style::default().add_modifier(Modifier::BOLD).fg(Color::White)),
+                    Span::raw("?"),
+                ]),
+                Line::from(Span::styled("(y) Yes / (N) No", Style::default().fg(Color::DarkGray))),
+            ];
+            let block = Block::default()
+                .borders(Borders::ALL)
+                .title(" Confirmation ")
+                .border_style(Style::default().fg(Color::DarkGray));
+            let p = Paragraph::new(text)
+                .block(block)
+                .alignment(ratatui::layout::Alignment::Center);
+            f.render_widget(p, area);
+        }
+
+        if let Some(input) = &self.input {
+            let area = centered_rect(60, 9, f.size());
+            f.render_widget(Clear, area);
+            let check = |on: bool| if on { "[x]" } else { "[ ]" };
+            let mut text = vec![
+                Line::from(vec![Span::raw("Message: "), Span::raw(input.message.as_str()), Span::raw("_")]),
+                Line::from(format!("{} Ctrl+U: include untracked files", check(input.include_untracked))),
+            ];
+            if !self.ctrl.selected_paths.is_empty() {
+                text.push(Line::from(format!(
+                    "{} Ctrl+O: only the {} file(s) marked in GitStage",
+                    check(input.only_selected),
+                    self.ctrl.selected_paths.len()
+                )));
+            }
+            let block = Block::default()
+                .borders(Borders::ALL)
+                .title(" New stash (Enter: stash, Esc: cancel) ")
+                .border_style(Style::default().fg(Color::DarkGray));
+            f.render_widget(Paragraph::new(text).block(block), area);
+        }
+    }
+
+    fn back(&self, ctx: &mut AppContext) -> anyhow::Result<Action> {
+        if self.origin == StashOrigin::GitStage {
+            if let Ok(state) = crate::ui::git_stage_ui::GitStageState::new(ctx) {
+                return Ok(Action::Switch(Screen::GitStage(Box::new(state))));
+            }
+        }
+        Ok(Action::Switch(Screen::Main(Box::new(crate::ui::main_ui::MainState::new(ctx)?))))
+    }
+
+    fn run(&mut self, action: StashAction) -> Action {
+        match self.ctrl.run(action) {
+            Ok(msg) => Action::Toast(msg),
+            Err(err) => {
+                app_log(&format!("Stash {} error: {}", action.verb(), err));
+                Action::Toast(format!("git stash {} failed: {}", action.verb(), err))
+            }
+        }
+    }
+
+    fn on_input_key(&mut self, key: KeyEvent) -> Action {
+        let Some(input) = self.input.as_mut() else {
+            return Action::None;
+        };
+        let ctrl_key = key.modifiers.contains(KeyModifiers::CONTROL);
+        match key.code {
+            KeyCode::Esc => self.input = None,
+            KeyCode::Char('u') if ctrl_key => input.include_untracked = !input.include_untracked,
+            KeyCode::Char('o') if ctrl_key && !self.ctrl.selected_paths.is_empty() => {
+                input.only_selected = !input.only_selected;
+            }
+            KeyCode::Char(c) if !ctrl_key => input.message.push(c),
+            KeyCode::Backspace => {
+                input.message.pop();
+            }
+            KeyCode::Enter => {
+                let Some(input) = self.input.take() else {
+                    return Action::None;
+                };
+                return match self.ctrl.push(&input.message, input.include_untracked, input.only_selected) {
+                    Ok(msg) => Action::Toast(msg),
+                    Err(err) => {
+                        app_log(&format!("Stash push error: {}", err));
+                        Action::Toast(format!("git stash push failed: {}", err))
+                    }
+                };
+            }
+            _ => {}
+        }
+        Action::None
+    }
+
+    pub fn on_key(&mut self, ctx: &mut AppContext, key: KeyEvent) -> anyhow::Result<Action> {
+        if self.input.is_some() {
+            return Ok(self.on_input_key(key));
+        }
+        if self.confirm_drop {
+            self.confirm_drop = false;
+            if matches!(key.code, KeyCode::Char('y') | KeyCode::Char('Y')) {
+                return Ok(self.run(StashAction::Drop));
+            }
+            return Ok(Action::None);
+        }
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return self.back(ctx),
+            KeyCode::Char('j') | KeyCode::Down => self.ctrl.next(),
+            KeyCode::Char('k') | KeyCode::Up => self.ctrl.prev(),
+            KeyCode::PageDown => self.ctrl.content_scroll = self.ctrl.content_scroll.saturating_add(10),
+            KeyCode::PageUp => self.ctrl.content_scroll = self.ctrl.content_scroll.saturating_sub(10),
+            KeyCode::Char('a') => return Ok(self.run(StashAction::Apply)),
+            KeyCode::Char('p') => return Ok(self.run(StashAction::Pop)),
+            KeyCode::Char('d') | KeyCode::Char('D') => self.confirm_drop = self.ctrl.focus_entry().is_some(),
+            KeyCode::Char('n') => {
+                self.input = Some(StashInput {
+                    message: String::new(),
+                    include_untracked: false,
+                    only_selected: !self.ctrl.selected_paths.is_empty(),
+                });
+            }
+            _ => {}
+        }
+        Ok(Action::None)
+    }
+
+    pub fn on_mouse(&mut self, _ctx: &mut AppContext, me: MouseEvent) -> anyhow::Result<Action> {
+        if let Some(area) = self.list_area {
+            if area.contains(mouse_pos(&me)) {
+                match me.kind {
+                    MouseEventKind::Down(_) => {
+                        let inner = area.inner(&Margin { horizontal: 0, vertical: 1 });
+                        if me.row >= inner.y && me.row < inner.y + inner.height {
+                            let idx = (me.row - inner.y) as usize + self.list_state.offset();
+                            while self.ctrl.selected_idx < idx && self.ctrl.selected_idx + 1 < self.ctrl.entries.len() {
+                                self.ctrl.next();
+                            }
+                            while self.ctrl.selected_idx > idx {
+                                self.ctrl.prev();
+                            }
+                        }
+                    }
+                    MouseEventKind::ScrollDown => self.ctrl.next(),
+                    MouseEventKind::ScrollUp => self.ctrl.prev(),
+                    _ => {}
+                }
+            }
+        }
+        if let Some(area) = self.content_area {
+            if area.contains(mouse_pos(&me)) {
+                if matches!(me.kind, MouseEventKind::ScrollDown) {
+                    self.ctrl.content_scroll = self.ctrl.content_scroll.saturating_add(3);
+                } else if matches!(me.kind, MouseEventKind::ScrollUp) {
+                    self.ctrl.content_scroll = self.ctrl.content_scroll.saturating_sub(3);
+                }
+            }
+        }
+        Ok(Action::None)
+    }
+}
+
+impl crate::ui::common::ScreenState for StashState {
+    fn render(&mut self, f: &mut ratatui::Frame) {
+        self.render(f);
+    }
+    fn on_key(&mut self, ctx: &mut AppContext, key: KeyEvent) -> anyhow::Result<Action> {
+        self.on_key(ctx, key)
+    }
+    fn on_mouse(&mut self, ctx: &mut AppContext, me: MouseEvent) -> anyhow::Result<Action> {
+        self.on_mouse(ctx, me)
+    }
+}
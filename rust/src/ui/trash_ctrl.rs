@@ -0,0 +1,184 @@
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::git;
+use crate::trash::{self, TrashEntry, TrashKind};
+
+const PREVIEW_LINES: usize = 1000;
+
+pub struct TrashCtrl {
+    pub root: PathBuf,
+    // Only discards from this repo are listed; all of them outside a repo.
+    pub repo: Option<PathBuf>,
+    pub entries: Vec<TrashEntry>,
+    pub selected_idx: usize,
+    pub preview: Vec<String>,
+    pub preview_scroll: u16,
+}
+
+pub fn format_age(secs: u64) -> String {
+    match secs {
+        0..=59 => "now".to_string(),
+        60..=3599 => format!("{}m ago", secs / 60),
+        3600..=86399 => format!("{}h ago", secs / 3600),
+        _ => format!("{}d ago", secs / 86400),
+    }
+}
+
+fn list_files(dir: &Path, base: &Path, out: &mut Vec<String>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let mut paths: Vec<PathBuf> = entries.flatten().map(|e| e.path()).collect();
+    paths.sort();
+    for path in paths {
+        if out.len() >= PREVIEW_LINES {
+            return;
+        }
+        if path.is_dir() {
+            list_files(&path, base, out);
+        } else if let Ok(rel) = path.strip_prefix(base) {
+            out.push(rel.to_string_lossy().to_string());
+        }
+    }
+}
+
+impl TrashCtrl {
+    pub fn new() -> anyhow::Result<Self> {
+        Self::with_root(trash::trash_root(), git::repo_root().ok())
+    }
+
+    pub fn with_root(root: PathBuf, repo: Option<PathBuf>) -> anyhow::Result<Self> {
+        let mut ctrl = Self {
+            root,
+            repo,
+            entries: Vec::new(),
+            selected_idx: 0,
+            preview: Vec::new(),
+            preview_scroll: 0,
+        };
+        ctrl.reload()?;
+        Ok(ctrl)
+    }
+
+    pub fn reload(&mut self) -> anyhow::Result<()> {
+        let repo = self.repo.as_ref().map(|r| r.to_string_lossy().to_string());
+        self.entries = trash::list(&self.root)?
+            .into_iter()
+            .filter(|e| repo.as_ref().is_none_or(|r| e.meta.repo == *r))
+            .collect();
+        self.selected_idx = self.selected_idx.min(self.entries.len().saturating_sub(1));
+        self.load_preview();
+        Ok(())
+    }
+
+    pub fn focus_entry(&self) -> Option<&TrashEntry> {
+        self.entries.get(self.selected_idx)
+    }
+
+    pub fn label(entry: &TrashEntry) -> String {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+        let kind = match entry.meta.kind {
+            TrashKind::File => "file ",
+            TrashKind::Dir => "dir  ",
+            TrashKind::Patch => "patch",
+        };
+        format!("{:>8}  {}  {}", format_age(now.saturating_sub(entry.meta.time)), kind, entry.display_path())
+    }
+
+    fn load_preview(&mut self) {
+        self.preview_scroll = 0;
+        let Some(entry) = self.focus_entry() else {
+            self.preview = vec!["< Trash is empty >".to_string()];
+            return;
+        };
+        let data = entry.data_path();
+        self.preview = match entry.meta.kind {
+            TrashKind::Dir => {
+                let mut out = Vec::new();
+                list_files(&data, &data, &mut out);
+                out
+            }
+            TrashKind::File | TrashKind::Patch => match std::fs::read(&data) {
+                Ok(bytes) => String::from_utf8_lossy(&bytes)
+                    .replace('\t', "    ")
+                    .lines()
+                    .take(PREVIEW_LINES)
+                    .map(|l| l.to_string())
+                    .collect(),
+                Err(err) => vec![format!("< {} >", err)],
+            },
+        };
+    }
+
+    pub fn next(&mut self) {
+        if self.selected_idx + 1 < self.entries.len() {
+            self.selected_idx += 1;
+            self.load_preview();
+        }
+    }
+
+    pub fn prev(&mut self) {
+        if self.selected_idx > 0 {
+            self.selected_idx -= 1;
+            self.load_preview();
+        }
+    }
+
+    pub fn restore(&mut self) -> anyhow::Result<String> {
+        let Some(entry) = self.focus_entry().cloned() else {
+            return Ok("Nothing to restore".to_string());
+        };
+        trash::restore(&entry)?;
+        self.reload()?;
+        Ok(format!("Restored: {}", entry.display_path()))
+    }
+
+    pub fn delete(&mut self) -> anyhow::Result<String> {
+        let Some(entry) = self.focus_entry().cloned() else {
+            return Ok("Nothing to delete".to_string());
+        };
+        trash::remove(&entry)?;
+        self.reload()?;
+        Ok(format!("Deleted from trash: {}", entry.display_path()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ui::common::TestEnv;
+
+    #[test]
+    fn test_trash_ctrl_filters_by_repo_and_restores() {
+        let env = TestEnv::setup("test_trash_ctrl");
+        let root = env.root.join("trash");
+        let repo = env.root.join("repo");
+        let other = env.root.join("other");
+        std::fs::create_dir_all(&repo).unwrap();
+        std::fs::create_dir_all(&other).unwrap();
+        std::fs::write(repo.join("a.txt"), "a\n").unwrap();
+        std::fs::write(other.join("b.txt"), "b\n").unwrap();
+        trash::save_path(&root, &repo, &repo.join("a.txt")).unwrap();
+        trash::save_path(&root, &other, &other.join("b.txt")).unwrap();
+        std::fs::remove_file(repo.join("a.txt")).unwrap();
+
+        let mut ctrl = TrashCtrl::with_root(root.clone(), Some(repo.clone())).unwrap();
+        assert_eq!(ctrl.entries.len(), 1);
+        assert_eq!(ctrl.preview, vec!["a".to_string()]);
+        assert!(TrashCtrl::label(&ctrl.entries[0]).ends_with("file   a.txt"));
+
+        ctrl.restore().unwrap();
+        assert!(ctrl.entries.is_empty());
+        assert_eq!(std::fs::read_to_string(repo.join("a.txt")).unwrap(), "a\n");
+        assert_eq!(TrashCtrl::with_root(root, None).unwrap().entries.len(), 1);
+    }
+
+    #[test]
+    fn test_format_age() {
+        assert_eq!(format_age(5), "now");
+        assert_eq!(format_age(125), "2m ago");
+        assert_eq!(format_age(7200), "2h ago");
+        assert_eq!(format_age(3 * 86400), "3d ago");
+    }
+}
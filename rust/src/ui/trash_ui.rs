@@ -0,0 +1,167 @@
+use crossterm::event::{KeyCode, KeyEvent, MouseEvent, MouseEventKind};
+use ratatui::layout::{Constraint, Direction, Layout, Margin, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span, Text};
+use ratatui::widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph};
+
+use crate::app::AppContext;
+use crate::trash::TrashKind;
+use crate::ui::common::{Action, Screen, centered_rect, format_diff_lines, mouse_pos};
+use crate::ui::trash_ctrl::TrashCtrl;
+
+pub struct TrashState {
+    pub ctrl: TrashCtrl,
+    pub confirm_delete: bool,
+    pub list_state: ListState,
+    pub list_area: Option<Rect>,
+    pub preview_area: Option<Rect>,
+}
+
+impl TrashState {
+    pub fn new() -> anyhow::Result<Self> {
+        Ok(Self {
+            ctrl: TrashCtrl::new()?,
+            confirm_delete: false,
+            list_state: ListState::default(),
+            list_area: None,
+            preview_area: None,
+        })
+    }
+
+    pub fn render(&mut self, f: &mut ratatui::Frame) {
+        let layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(1),
+                Constraint::Percentage(35),
+                Constraint::Min(3),
+            ])
+            .split(f.size());
+
+        let title = Paragraph::new(Line::from(Span::styled(
+            " >> Trash (Enter/r: restore, X: delete, q: back)",
+            Style::default().fg(Color::Green).add_modifier(Modifier::BOLD),
+        )));
+        f.render_widget(title, layout[0]);
+
+        let items: Vec<ListItem> = self.ctrl.entries.iter().map(|e| ListItem::new(TrashCtrl::label(e))).collect();
+        self.list_state.select(if items.is_empty() { None } else { Some(self.ctrl.selected_idx) });
+        let list = List::new(items)
+            .block(Block::default().title("Discarded"))
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        f.render_stateful_widget(list, layout[1], &mut self.list_state);
+        self.list_area = Some(layout[1]);
+
+        let is_patch = self.ctrl.focus_entry().is_some_and(|e| e.meta.kind == TrashKind::Patch);
+        let lines = if is_patch {
+            format_diff_lines(&self.ctrl.preview, layout[2].width)
+        } else {
+            self.ctrl.preview.iter().map(|l| Line::from(l.as_str())).collect()
+        };
+        let view = Paragraph::new(Text::from(lines))
+            .block(Block::default().title("Content"))
+            .scroll((self.ctrl.preview_scroll, 0));
+        f.render_widget(view, layout[2]);
+        self.preview_area = Some(layout[2]);
+
+        if self.confirm_delete {
+            let area = centered_rect(50, 7, f.size());
+            f.render_widget(Clear, area);
+            let label = self.ctrl.focus_entry().map(TrashCtrl::label).unwrap_or_default();
+            let text = vec![
+                Line::from(vec![
+                    Span::raw("Delete "),
+                    Span::styled(label, Style::default().add_modifier(Modifier::BOLD).fg(Color::White)),
+                    Span::raw(" for good?"),
+                ]),
+                Line::from(Span::styled("(y) Yes / (N) No", Style::default().fg(Color::DarkGray))),
+            ];
+            let block = Block::default()
+                .borders(Borders::ALL)
+                .title(" Confirmation ")
+                .border_style(Style::default().fg(Color::DarkGray));
+            let p = Paragraph::new(text)
+                .block(block)
+                .alignment(ratatui::layout::Alignment::Center);
+            f.render_widget(p, area);
+        }
+    }
+
+    fn back(ctx: &mut AppContext) -> anyhow::Result<Action> {
+        Ok(match crate::ui::git_stage_ui::GitStageState::new(ctx) {
+            Ok(state) => Action::Switch(Screen::GitStage(Box::new(state))),
+            Err(_) => Action::Switch(Screen::Main(Box::new(crate::ui::main_ui::MainState::new(ctx)?))),
+        })
+    }
+
+    pub fn on_key(&mut self, ctx: &mut AppContext, key: KeyEvent) -> anyhow::Result<Action> {
+        if self.confirm_delete {
+            self.confirm_delete = false;
+            if matches!(key.code, KeyCode::Char('y') | KeyCode::Char('Y')) {
+                let msg = self.ctrl.delete().unwrap_or_else(|err| format!("Delete failed: {}", err));
+                return Ok(Action::Toast(msg));
+            }
+            return Ok(Action::None);
+        }
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Self::back(ctx),
+            KeyCode::Char('j') | KeyCode::Down => self.ctrl.next(),
+            KeyCode::Char('k') | KeyCode::Up => self.ctrl.prev(),
+            KeyCode::PageDown => self.ctrl.preview_scroll = self.ctrl.preview_scroll.saturating_add(10),
+            KeyCode::PageUp => self.ctrl.preview_scroll = self.ctrl.preview_scroll.saturating_sub(10),
+            KeyCode::Enter | KeyCode::Char('r') => {
+                let msg = self.ctrl.restore().unwrap_or_else(|err| format!("Restore failed: {}", err));
+                return Ok(Action::Toast(msg));
+            }
+            KeyCode::Char('X') => self.confirm_delete = self.ctrl.focus_entry().is_some(),
+            _ => {}
+        }
+        Ok(Action::None)
+    }
+
+    pub fn on_mouse(&mut self, _ctx: &mut AppContext, me: MouseEvent) -> anyhow::Result<Action> {
+        if let Some(area) = self.list_area {
+            if area.contains(mouse_pos(&me)) {
+                match me.kind {
+                    MouseEventKind::Down(_) => {
+                        let inner = area.inner(&Margin { horizontal: 0, vertical: 1 });
+                        if me.row >= inner.y && me.row < inner.y + inner.height {
+                            let idx = (me.row - inner.y) as usize + self.list_state.offset();
+                            while self.ctrl.selected_idx < idx && self.ctrl.selected_idx + 1 < self.ctrl.entries.len() {
+                                self.ctrl.next();
+                            }
+                            while self.ctrl.selected_idx > idx {
+                                self.ctrl.prev();
+                            }
+                        }
+                    }
+                    MouseEventKind::ScrollDown => self.ctrl.next(),
+                    MouseEventKind::ScrollUp => self.ctrl.prev(),
+                    _ => {}
+                }
+            }
+        }
+        if let Some(area) = self.preview_area {
+            if area.contains(mouse_pos(&me)) {
+                if matches!(me.kind, MouseEventKind::ScrollDown) {
+                    self.ctrl.preview_scroll = self.ctrl.preview_scroll.saturating_add(3);
+                } else if matches!(me.kind, MouseEventKind::ScrollUp) {
+                    self.ctrl.preview_scroll = self.ctrl.preview_scroll.saturating_sub(3);
+                }
+            }
+        }
+        Ok(Action::None)
+    }
+}
+
+impl crate::ui::common::ScreenState for TrashState {
+    fn render(&mut self, f: &mut ratatui::Frame) {
+        self.render(f);
+    }
+    fn on_key(&mut self, ctx: &mut AppContext, key: KeyEvent) -> anyhow::Result<Action> {
+        self.on_key(ctx, key)
+    }
+    fn on_mouse(&mut self, ctx: &mut AppContext, me: MouseEvent) -> anyhow::Result<Action> {
+        self.on_mouse(ctx, me)
+    }
+}
@@ -129,3 +129,138 @@ pub fn walk_dirs(root: &Path, ignore: &[&str], limit: usize) -> Vec<PathBuf> {
     out
 }
 
+
+// Days since 1970-01-01 to (year, month, day), proleptic Gregorian.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+// Inverse of `civil_from_days`.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y.rem_euclid(400);
+    let mp = if month > 2 { month - 3 } else { month + 9 } as i64;
+    let doy = (153 * mp + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+// Epoch seconds for "2026-01-01" (UTC midnight) or an age like "2w" / "3d" / "12h" back from `now`.
+// Units: h, d (default), w, m (30 days), y (365 days). None when unparsable or out of range.
+pub fn parse_date_bound(text: &str, now: i64) -> Option<i64> {
+    let parts: Vec<&str> = text.split('-').collect();
+    if let [y, m, d] = parts[..] {
+        let (year, month, day) = (y.parse().ok()?, m.parse().ok()?, d.parse().ok()?);
+        if !(1..=9999).contains(&year) || !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+            return None;
+        }
+        return Some(days_from_civil(year, month, day) * 86_400);
+    }
+    let split = text.find(|c: char| !c.is_ascii_digit()).unwrap_or(text.len());
+    let n: i64 = text[..split].parse().ok()?;
+    let unit = match &text[split..] {
+        "h" => 3_600,
+        "" | "d" => 86_400,
+        "w" => 604_800,
+        "m" => 2_592_000,
+        "y" => 31_536_000,
+        _ => return None,
+    };
+    now.checked_sub(n.checked_mul(unit)?)
+}
+
+// "2026-02-15 14:03" in the zone given by `offset` (seconds east of UTC).
+pub fn format_datetime(secs: i64, offset: i32) -> String {
+    let local = secs + offset as i64;
+    let (year, month, day) = civil_from_days(local.div_euclid(86_400));
+    let rest = local.rem_euclid(86_400);
+    format!("{:04}-{:02}-{:02} {:02}:{:02}", year, month, day, rest / 3600, rest % 3600 / 60)
+}
+
+// "+0900" style offset, as git prints it.
+pub fn format_offset(offset: i32) -> String {
+    let sign = if offset < 0 { '-' } else { '+' };
+    let abs = offset.unsigned_abs();
+    format!("{}{:02}{:02}", sign, abs / 3600, abs % 3600 / 60)
+}
+
+// "3 days ago", with git's thresholds for switching units.
+pub fn format_relative(secs: i64, now: i64) -> String {
+    let diff = now - secs;
+    if diff < 0 {
+        return "in the future".to_string();
+    }
+    let (n, unit) = match diff {
+        0..=89 => return "just now".to_string(),
+        90..=5_399 => ((diff + 30) / 60, "minute"),
+        5_400..=129_599 => ((diff + 1_800) / 3_600, "hour"),
+        129_600..=1_209_599 => ((diff + 43_200) / 86_400, "day"),
+        1_209_600..=6_047_999 => ((diff + 302_400) / 604_800, "week"),
+        6_048_000..=31_535_999 => ((diff + 1_296_000) / 2_592_000, "month"),
+        _ => (diff / 31_536_000, "year"),
+    };
+    format!("{} {}{} ago", n, unit, if n == 1 { "" } else { "s" })
+}
+
+pub fn now_secs() -> i64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs() as i64)
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_datetime_and_offset() {
+        // 2026-02-15 05:03:20 UTC, committed at +0900.
+        let time = 1_771_131_800;
+        assert_eq!(format_datetime(time, 9 * 3600), "2026-02-15 14:03");
+        assert_eq!(format_datetime(time, -5 * 3600), "2026-02-15 00:03");
+        assert_eq!(format_offset(9 * 3600), "+0900");
+        assert_eq!(format_offset(-(5 * 3600 + 1800)), "-0530");
+        assert_eq!(format_datetime(951_782_400, 0), "2000-02-29 00:00");
+    }
+
+    #[test]
+    fn test_format_relative() {
+        let time = 1_771_131_800;
+        assert_eq!(format_relative(time, time + 30), "just now");
+        assert_eq!(format_relative(time, time + 3600), "60 minutes ago");
+        assert_eq!(format_relative(time, time + 3 * 86_400), "3 days ago");
+        assert_eq!(format_relative(time, time + 21 * 86_400), "3 weeks ago");
+        assert_eq!(format_relative(time, time + 400 * 86_400), "1 year ago");
+        assert_eq!(format_relative(time + 60, time), "in the future");
+    }
+
+    #[test]
+    fn test_parse_date_bound() {
+        let now = 1_771_131_800;
+        assert_eq!(parse_date_bound("2026-02-15", now), Some(1_771_113_600));
+        assert_eq!(parse_date_bound("1970-01-01", now), Some(0));
+        assert_eq!(parse_date_bound("12h", now), Some(now - 12 * 3_600));
+        assert_eq!(parse_date_bound("3", now), Some(now - 3 * 86_400));
+        assert_eq!(parse_date_bound("2w", now), Some(now - 2 * 604_800));
+        assert_eq!(parse_date_bound("1m", now), Some(now - 2_592_000));
+        assert_eq!(parse_date_bound("1y", now), Some(now - 31_536_000));
+        assert_eq!(parse_date_bound("2026-13-01", now), None);
+        assert_eq!(parse_date_bound("3x", now), None);
+        assert_eq!(parse_date_bound("", now), None);
+        // Out of range instead of overflowing.
+        assert_eq!(parse_date_bound("9999999999999y", now), None);
+        assert_eq!(parse_date_bound("99999999999999999999d", now), None);
+        assert_eq!(parse_date_bound("99999999999999-01-01", now), None);
+    }
+}